@@ -8,11 +8,30 @@ use qrcode_core::{
     QrRenderConfig,
     FinderShape,
     DataShape,
+    DataRenderMode,
     FinderStyle,
-    DataStyle
+    DataStyle,
+    AlignmentShape,
+    AlignmentStyle,
+    ErrorCorrectionLevel,
+    Logo,
+    LogoImage,
+    Background,
+    Color,
+    ContrastSeverity,
+    UnitPath,
+    QrEncodeOptions,
+    QrError,
+    MaskSelection,
+    VersionConstraint,
+    MIN_VERSION,
+    MAX_VERSION,
+    SvgSize,
+    DEFAULT_MODULE_SIZE,
+    DEFAULT_PRECISION,
+    DEFAULT_QUIET_ZONE,
 };
 use base64::{engine::general_purpose, Engine as _};
-use console_log;
 use std::sync::Once;
 
 static INIT_LOGGER: Once = Once::new();
@@ -37,6 +56,45 @@ mod tests {
         let result = add(2, 2);
         assert_eq!(result, 4);
     }
+
+    #[test]
+    fn refuses_misspelt_settings() {
+        let config = || QrConfig::new("Square".to_string(), "Dot".to_string(), "black".to_string(), "black".to_string());
+        assert!(convert_config(&config()).is_ok());
+
+        let mut misspelt = vec![
+            QrConfig::new("Sqaure".to_string(), "Dot".to_string(), "black".to_string(), "black".to_string()),
+            QrConfig::new("Square".to_string(), "Dots".to_string(), "black".to_string(), "black".to_string()),
+        ];
+        let mut eye = config();
+        eye.set_finder_eye_shape("Circle".to_string());
+        let mut mode = config();
+        mode.set_data_render_mode("Merged".to_string());
+        let mut alignment = config();
+        alignment.set_alignment_shape("round".to_string());
+        let mut error_correction = config();
+        error_correction.set_error_correction("Hihg".to_string());
+        misspelt.extend([eye, mode, alignment, error_correction]);
+
+        for (config, value) in misspelt.iter().zip(["Sqaure", "Dots", "Circle", "Merged", "round", "Hihg"]) {
+            match convert_config(config) {
+                Err(QrError::InvalidConfig(message)) => assert!(message.contains(&format!("\"{value}\"")), "{message}"),
+                _ => panic!("\"{value}\" was accepted"),
+            }
+        }
+    }
+
+    #[cfg(feature = "verify")]
+    #[test]
+    fn verifies_rendered_codes() {
+        // The eye takes the frame's shape unless set, so every finder shape must scan as it is
+        for finder in ["Square", "Dot", "Rounded", "Triangle"] {
+            let mut config = QrConfig::new(finder.to_string(), "Dot".to_string(), "black".to_string(), "black".to_string());
+            config.set_verify(true);
+            let config = convert_config(&config).unwrap();
+            assert!(render_qr_code_svg("https://jedi.org", Some(&config)).is_ok(), "{finder}");
+        }
+    }
 }
 
 #[wasm_bindgen]
@@ -48,8 +106,37 @@ pub fn hello() -> String {
 pub struct QrConfig {
     finder_shape: String,
     data_shape: String,
+    data_render_mode: String,
+    line_thickness: f32,
     finder_color: String,
     data_color: String,
+    error_correction: String,
+    min_version: u8,
+    max_version: u8,
+    quiet_zone: usize,
+    mask: Option<u8>,
+    alignment_shape: String,
+    alignment_color: String,
+    finder_eye_shape: String,
+    finder_eye_color: String,
+    logo_svg: Option<String>,
+    logo_image: Option<Vec<u8>>,
+    logo_size: f32,
+    logo_padding: usize,
+    logo_plate: Option<String>,
+    data_path: Option<String>,
+    finder_path: Option<String>,
+    finder_eye_path: Option<String>,
+    module_size: f32,
+    precision: u8,
+    snap_to_pixels: bool,
+    svg_size: String,
+    background: String,
+    background_image: Option<Vec<u8>>,
+    matte: String,
+    inverted: bool,
+    verify: bool,
+    id_prefix: String,
 }
 
 #[wasm_bindgen]
@@ -57,48 +144,386 @@ impl QrConfig {
     #[wasm_bindgen(constructor)]
     pub fn new(finder_shape: String, data_shape: String, finder_color: String, data_color: String) -> Self {
         QrConfig {
+            alignment_shape: "Square".to_string(),
+            // Alignment patterns follow the finder colour until configured separately
+            alignment_color: finder_color.clone(),
+            // The finder eye matches the frame until configured separately
+            finder_eye_shape: finder_shape.clone(),
+            finder_eye_color: finder_color.clone(),
             finder_shape,
             data_shape,
+            data_render_mode: "MergedPath".to_string(),
+            line_thickness: 0.8,
             finder_color,
             data_color,
+            error_correction: "Medium".to_string(),
+            min_version: MIN_VERSION,
+            max_version: MAX_VERSION,
+            quiet_zone: DEFAULT_QUIET_ZONE,
+            mask: None,
+            logo_svg: None,
+            logo_image: None,
+            logo_size: 0.2,
+            logo_padding: 1,
+            logo_plate: None,
+            data_path: None,
+            finder_path: None,
+            finder_eye_path: None,
+            module_size: DEFAULT_MODULE_SIZE,
+            precision: DEFAULT_PRECISION,
+            snap_to_pixels: false,
+            svg_size: "Pixels".to_string(),
+            background: "white".to_string(),
+            background_image: None,
+            matte: "white".to_string(),
+            inverted: false,
+            verify: false,
+            id_prefix: "qr".to_string(),
         }
     }
+
+    /// Sets how data modules are written: "MergedPath" (one path for Square, Liquid and CustomPath data) or "PerModule"
+    #[wasm_bindgen(setter)]
+    pub fn set_data_render_mode(&mut self, data_render_mode: String) {
+        self.data_render_mode = data_render_mode;
+    }
+
+    /// Sets the bar thickness of the line data shapes as a fraction of the module size (default 0.8)
+    #[wasm_bindgen(setter)]
+    pub fn set_line_thickness(&mut self, line_thickness: f32) {
+        self.line_thickness = line_thickness;
+    }
+
+    /// Sets the error correction level: "Low", "Medium", "Quartile" or "High"
+    #[wasm_bindgen(setter)]
+    pub fn set_error_correction(&mut self, error_correction: String) {
+        self.error_correction = error_correction;
+    }
+
+    /// Forces a fixed QR version (1-40) so every code has the same module count
+    #[wasm_bindgen(setter)]
+    pub fn set_version(&mut self, version: u8) {
+        self.min_version = version;
+        self.max_version = version;
+    }
+
+    /// Sets the smallest QR version (1-40) the encoder may choose
+    #[wasm_bindgen(setter)]
+    pub fn set_min_version(&mut self, min_version: u8) {
+        self.min_version = min_version;
+    }
+
+    /// Sets the largest QR version (1-40) the encoder may choose
+    #[wasm_bindgen(setter)]
+    pub fn set_max_version(&mut self, max_version: u8) {
+        self.max_version = max_version;
+    }
+
+    /// Sets the width of the light margin around the code, in modules (default 4)
+    #[wasm_bindgen(setter)]
+    pub fn set_quiet_zone(&mut self, quiet_zone: usize) {
+        self.quiet_zone = quiet_zone;
+    }
+
+    /// Forces a mask pattern (0-7), or lets the library pick the lowest-penalty mask when unset
+    #[wasm_bindgen(setter)]
+    pub fn set_mask(&mut self, mask: Option<u8>) {
+        self.mask = mask;
+    }
+
+    /// Sets the shape of the finder patterns' inner eye: "Square", "Dot", "Rounded", "Triangle" or "CustomPath"
+    #[wasm_bindgen(setter)]
+    pub fn set_finder_eye_shape(&mut self, finder_eye_shape: String) {
+        self.finder_eye_shape = finder_eye_shape;
+    }
+
+    /// Sets the colour of the finder patterns' inner eye
+    #[wasm_bindgen(setter)]
+    pub fn set_finder_eye_color(&mut self, finder_eye_color: String) {
+        self.finder_eye_color = finder_eye_color;
+    }
+
+    /// Sets the alignment pattern shape: "Square", "Rounded" or "Dot"
+    #[wasm_bindgen(setter)]
+    pub fn set_alignment_shape(&mut self, alignment_shape: String) {
+        self.alignment_shape = alignment_shape;
+    }
+
+    /// Sets the alignment pattern colour
+    #[wasm_bindgen(setter)]
+    pub fn set_alignment_color(&mut self, alignment_color: String) {
+        self.alignment_color = alignment_color;
+    }
+
+    /// Places an SVG document, or a fragment of SVG elements scaled to fit, in the centre of the
+    /// code, replacing any raster logo
+    #[wasm_bindgen(setter)]
+    pub fn set_logo_svg(&mut self, logo_svg: Option<String>) {
+        self.logo_svg = logo_svg;
+        self.logo_image = None;
+    }
+
+    /// Places a PNG or JPEG image in the centre of the code, replacing any SVG logo
+    #[wasm_bindgen(setter)]
+    pub fn set_logo_image(&mut self, logo_image: Option<Vec<u8>>) {
+        self.logo_image = logo_image;
+        self.logo_svg = None;
+    }
+
+    /// Sets the logo size as a fraction of the code, excluding the quiet zone (default 0.2)
+    #[wasm_bindgen(setter)]
+    pub fn set_logo_size(&mut self, logo_size: f32) {
+        self.logo_size = logo_size;
+    }
+
+    /// Sets the clear margin around the logo, in modules (default 1)
+    #[wasm_bindgen(setter)]
+    pub fn set_logo_padding(&mut self, logo_padding: usize) {
+        self.logo_padding = logo_padding;
+    }
+
+    /// Sets the colour of a plate drawn behind the logo, or removes it when unset
+    #[wasm_bindgen(setter)]
+    pub fn set_logo_plate(&mut self, logo_plate: Option<String>) {
+        self.logo_plate = logo_plate;
+    }
+
+    /// Sets the background colour (default "white"), or "transparent" for no background
+    #[wasm_bindgen(setter)]
+    pub fn set_background(&mut self, background: String) {
+        self.background = background;
+    }
+
+    /// Covers the background with a PNG or JPEG image, cropped to keep its aspect ratio,
+    /// in place of the background colour
+    #[wasm_bindgen(setter)]
+    pub fn set_background_image(&mut self, background_image: Option<Vec<u8>>) {
+        self.background_image = background_image;
+    }
+
+    /// Sets the colour JPEG output shows where the background is transparent (default "white")
+    #[wasm_bindgen(setter)]
+    pub fn set_matte(&mut self, matte: String) {
+        self.matte = matte;
+    }
+
+    /// Draws light modules on a dark ground: the data colour fills the quiet zone and light
+    /// modules, and the background shows through the dark ones
+    #[wasm_bindgen(setter)]
+    pub fn set_inverted(&mut self, inverted: bool) {
+        self.inverted = inverted;
+    }
+
+    /// Decodes each rendered code before returning it, failing with a "VERIFICATION_FAILED"
+    /// error when it does not read back as the input. Needs the `verify` feature, on by
+    /// default, and fails with an "INVALID_CONFIG" error in builds without it.
+    #[wasm_bindgen(setter)]
+    pub fn set_verify(&mut self, verify: bool) {
+        self.verify = verify;
+    }
+
+    /// Sets the prefix of every id in the SVG (default "qr"); give each code inlined into the
+    /// same page its own so their gradients do not clash
+    #[wasm_bindgen(setter)]
+    pub fn set_id_prefix(&mut self, id_prefix: String) {
+        self.id_prefix = id_prefix;
+    }
+
+    /// Sets the width and height of each module in pixels (default 10), which may be fractional
+    #[wasm_bindgen(setter)]
+    pub fn set_module_size(&mut self, module_size: f32) {
+        self.module_size = module_size;
+    }
+
+    /// Sets the number of decimal places kept in SVG coordinates (default 2, at most 6)
+    #[wasm_bindgen(setter)]
+    pub fn set_precision(&mut self, precision: u8) {
+        self.precision = precision;
+    }
+
+    /// Sets whether PNG and JPEG output lays modules out on whole device pixels, widening the quiet
+    /// zone with any leftover pixels, so module edges stay sharp
+    #[wasm_bindgen(setter)]
+    pub fn set_snap_to_pixels(&mut self, snap_to_pixels: bool) {
+        self.snap_to_pixels = snap_to_pixels;
+    }
+
+    /// Sets the size the SVG declares: "Pixels" (default), "ViewBox" to scale with its container,
+    /// or a length such as "40mm", "1.5in" or "100%"
+    #[wasm_bindgen(setter)]
+    pub fn set_svg_size(&mut self, svg_size: String) {
+        self.svg_size = svg_size;
+    }
+
+    /// Sets the SVG path data, in a 0-1 unit box, drawn in each module when the data shape is "CustomPath"
+    #[wasm_bindgen(setter)]
+    pub fn set_data_path(&mut self, data_path: Option<String>) {
+        self.data_path = data_path;
+    }
+
+    /// Sets the SVG path data, in a 0-1 unit box, stretched over each finder frame when its shape is "CustomPath"
+    #[wasm_bindgen(setter)]
+    pub fn set_finder_path(&mut self, finder_path: Option<String>) {
+        self.finder_path = finder_path;
+    }
+
+    /// Sets the SVG path data, in a 0-1 unit box, stretched over each finder eye when its shape is "CustomPath"
+    #[wasm_bindgen(setter)]
+    pub fn set_finder_eye_path(&mut self, finder_eye_path: Option<String>) {
+        self.finder_eye_path = finder_eye_path;
+    }
+}
+
+/// Converts a core error into a JS `Error` carrying a machine-readable `code` property
+fn to_js_error(error: QrError) -> JsValue {
+    let js_error = js_sys::Error::new(&error.to_string());
+    js_error.set_name("QrError");
+    // Setting a property on a freshly created Error object cannot fail
+    let _ = js_sys::Reflect::set(&js_error, &JsValue::from_str("code"), &JsValue::from_str(error.code()));
+    js_error.into()
+}
+
+/// Parses the path data of a "CustomPath" shape, which must have been set
+fn convert_path(path: &Option<String>, setter: &str) -> Result<UnitPath, QrError> {
+    match path {
+        Some(path) => UnitPath::parse(path),
+        None => Err(QrError::InvalidConfig(format!("a \"CustomPath\" shape needs {setter} to be set"))),
+    }
 }
 
-fn convert_config(config: &QrConfig) -> QrRenderConfig {
-    let finder_shape = match config.finder_shape.as_str() {
+fn convert_finder_shape(shape: &str, path: &Option<String>, setter: &str) -> Result<FinderShape, QrError> {
+    Ok(match shape {
         "Square" => FinderShape::Square,
         "Dot" => FinderShape::Dot,
         "Rounded" => FinderShape::Rounded,
         "Triangle" => FinderShape::Triangle,
-        _ => FinderShape::Square,
+        "CustomPath" => FinderShape::CustomPath(convert_path(path, setter)?),
+        _ => return Err(QrError::InvalidConfig(format!("unknown finder shape \"{shape}\""))),
+    })
+}
+
+/// Parses an SVG size setting such as "Pixels", "ViewBox" or "40mm"
+fn convert_svg_size(svg_size: &str) -> Result<SvgSize, QrError> {
+    let length = |unit: &str| {
+        svg_size
+            .strip_suffix(unit)
+            .and_then(|number| number.trim().parse::<f32>().ok())
     };
+    if let Some(size) = length("mm") {
+        return Ok(SvgSize::Millimetres(size));
+    }
+    if let Some(size) = length("in") {
+        return Ok(SvgSize::Inches(size));
+    }
+    if let Some(size) = length("%") {
+        return Ok(SvgSize::Percent(size));
+    }
+    match svg_size {
+        "Pixels" => Ok(SvgSize::Pixels),
+        "ViewBox" => Ok(SvgSize::ViewBoxOnly),
+        _ => Err(QrError::InvalidConfig(format!("unknown SVG size \"{svg_size}\""))),
+    }
+}
+
+fn convert_config(config: &QrConfig) -> Result<QrRenderConfig, QrError> {
 
     let data_shape = match config.data_shape.as_str() {
         "Square" => DataShape::Square,
         "Dot" => DataShape::Dot,
         "Rounded" => DataShape::Rounded,
         "Triangle" => DataShape::Triangle,
-        _ => DataShape::Dot,
+        "Liquid" => DataShape::Liquid,
+        "HorizontalLines" => DataShape::HorizontalLines { thickness: config.line_thickness },
+        "VerticalLines" => DataShape::VerticalLines { thickness: config.line_thickness },
+        "CustomPath" => DataShape::CustomPath(convert_path(&config.data_path, "dataPath")?),
+        shape => return Err(QrError::InvalidConfig(format!("unknown data shape \"{shape}\""))),
     };
 
-    QrRenderConfig {
-        finder_shape,
+    let data_render_mode = match config.data_render_mode.as_str() {
+        "MergedPath" => DataRenderMode::MergedPath,
+        "PerModule" => DataRenderMode::PerModule,
+        mode => return Err(QrError::InvalidConfig(format!("unknown data render mode \"{mode}\""))),
+    };
+
+    let alignment_shape = match config.alignment_shape.as_str() {
+        "Square" => AlignmentShape::Square,
+        "Rounded" => AlignmentShape::Rounded,
+        "Dot" => AlignmentShape::Dot,
+        shape => return Err(QrError::InvalidConfig(format!("unknown alignment shape \"{shape}\""))),
+    };
+
+    let error_correction = match config.error_correction.as_str() {
+        "Low" => ErrorCorrectionLevel::Low,
+        "Medium" => ErrorCorrectionLevel::Medium,
+        "Quartile" => ErrorCorrectionLevel::Quartile,
+        "High" => ErrorCorrectionLevel::High,
+        level => return Err(QrError::InvalidConfig(format!("unknown error correction level \"{level}\""))),
+    };
+
+    let version = match (config.min_version, config.max_version) {
+        (MIN_VERSION, MAX_VERSION) => VersionConstraint::Auto,
+        (min, max) if min == max => VersionConstraint::Fixed(min),
+        (min, max) => VersionConstraint::Range { min, max },
+    };
+
+    let logo_image = match (&config.logo_svg, &config.logo_image) {
+        (Some(svg), _) => Some(LogoImage::Svg(svg.clone())),
+        (None, Some(bytes)) => Some(LogoImage::Raster(bytes.clone())),
+        (None, None) => None,
+    };
+    let plate = config.logo_plate.as_deref().map(Color::parse).transpose()?;
+    let logo = logo_image.map(|image| Logo {
+        image,
+        size: config.logo_size,
+        padding: config.logo_padding,
+        plate,
+    });
+
+    let background = match (&config.background_image, config.background.as_str()) {
+        (Some(bytes), _) => Background::Image(LogoImage::Raster(bytes.clone())),
+        (None, "transparent" | "none") => Background::None,
+        (None, color) => Background::color(color)?,
+    };
+
+    Ok(QrRenderConfig {
+        encoding: QrEncodeOptions {
+            error_correction,
+            version,
+            mask: config.mask.map_or(MaskSelection::Auto, MaskSelection::Fixed),
+        },
+        finder_frame_shape: convert_finder_shape(&config.finder_shape, &config.finder_path, "finderPath")?,
+        finder_eye_shape: convert_finder_shape(&config.finder_eye_shape, &config.finder_eye_path, "finderEyePath")?,
         data_shape,
-        finder_styling: FinderStyle::Color(config.finder_color.clone()),
-        data_styling: DataStyle::Color(config.data_color.clone()),
-    }
+        data_render_mode,
+        finder_frame_styling: FinderStyle::color(&config.finder_color)?,
+        finder_eye_styling: FinderStyle::color(&config.finder_eye_color)?,
+        data_styling: DataStyle::color(&config.data_color)?,
+        alignment_shape,
+        alignment_styling: AlignmentStyle::color(&config.alignment_color)?,
+        quiet_zone: config.quiet_zone,
+        logo,
+        module_size: config.module_size,
+        precision: config.precision,
+        snap_to_pixels: config.snap_to_pixels,
+        svg_size: convert_svg_size(&config.svg_size)?,
+        background,
+        matte: Color::parse(&config.matte)?,
+        inverted: config.inverted,
+        verify: config.verify,
+        id_prefix: config.id_prefix.clone(),
+    })
 }
 
 /// Renders a QR code as SVG and returns the result as a string
 #[wasm_bindgen]
-pub fn render_qr_svg(url: &str, config: Option<QrConfig>) -> String {
+pub fn render_qr_svg(url: &str, config: Option<QrConfig>) -> Result<String, JsValue> {
     init_logger();
-    let qr_config = config.map(|c| convert_config(&c));
-    let result = render_qr_code_svg(url, qr_config.as_ref());
+    let qr_config = config.map(|c| convert_config(&c)).transpose().map_err(to_js_error)?;
+    let result = render_qr_code_svg(url, qr_config.as_ref())
+        .map_err(to_js_error)?;
     match result.data {
-        QrCodeOutput::Svg(svg) => svg,
-        _ => panic!("Expected SVG output"),
+        QrCodeOutput::Svg(svg) => Ok(svg),
+        _ => Err(to_js_error(QrError::Render("Expected SVG output".to_string()))),
     }
 }
 
@@ -106,15 +531,19 @@ pub fn render_qr_svg(url: &str, config: Option<QrConfig>) -> String {
 #[wasm_bindgen]
 pub fn render_qr_png(url: &str, config: Option<QrConfig>) -> Result<String, JsValue> {
     init_logger();
-    let qr_config = config.map(|c| convert_config(&c));
-    let matrix = generate_qr_matrix(url);
-    let size = (matrix.len() * 10) as u32;
+    let qr_config = config.map(|c| convert_config(&c)).transpose().map_err(to_js_error)?;
+    let encoding = qr_config.as_ref().map(|c| c.encoding.clone()).unwrap_or_default();
+    let quiet_zone = qr_config.as_ref().map_or(DEFAULT_QUIET_ZONE, |c| c.quiet_zone);
+    let module_size = qr_config.as_ref().map_or(DEFAULT_MODULE_SIZE, |c| c.module_size);
+    let matrix = generate_qr_matrix(url, &encoding)
+        .map_err(to_js_error)?;
+    let size = ((matrix.width() + 2 * quiet_zone) as f32 * module_size).round() as u32;
     let result = render_qr_code(url, qr_config.as_ref(), RasterFormat::Png, size)
-        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        .map_err(to_js_error)?;
     
     match result.data {
         QrCodeOutput::Raster(data, _) => Ok(general_purpose::STANDARD.encode(data)),
-        _ => Err(JsValue::from_str("Expected raster output")),
+        _ => Err(to_js_error(QrError::Render("Expected raster output".to_string()))),
     }
 }
 
@@ -122,23 +551,253 @@ pub fn render_qr_png(url: &str, config: Option<QrConfig>) -> Result<String, JsVa
 #[wasm_bindgen]
 pub fn render_qr_jpeg(url: &str, config: Option<QrConfig>) -> Result<String, JsValue> {
     init_logger();
-    let qr_config = config.map(|c| convert_config(&c));
-    let matrix = generate_qr_matrix(url);
-    let size = (matrix.len() * 10) as u32;
+    let qr_config = config.map(|c| convert_config(&c)).transpose().map_err(to_js_error)?;
+    let encoding = qr_config.as_ref().map(|c| c.encoding.clone()).unwrap_or_default();
+    let quiet_zone = qr_config.as_ref().map_or(DEFAULT_QUIET_ZONE, |c| c.quiet_zone);
+    let module_size = qr_config.as_ref().map_or(DEFAULT_MODULE_SIZE, |c| c.module_size);
+    let matrix = generate_qr_matrix(url, &encoding)
+        .map_err(to_js_error)?;
+    let size = ((matrix.width() + 2 * quiet_zone) as f32 * module_size).round() as u32;
     let result = render_qr_code(url, qr_config.as_ref(), RasterFormat::Jpeg, size)
-        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        .map_err(to_js_error)?;
     
     match result.data {
         QrCodeOutput::Raster(data, _) => Ok(general_purpose::STANDARD.encode(data)),
-        _ => Err(JsValue::from_str("Expected raster output")),
+        _ => Err(to_js_error(QrError::Render("Expected raster output".to_string()))),
     }
 }
 
 /// Returns the dimensions of a QR code for a given URL
 #[wasm_bindgen]
-pub fn get_qr_dimensions(url: &str, config: Option<QrConfig>) -> Vec<u32> {
+pub fn get_qr_dimensions(url: &str, config: Option<QrConfig>) -> Result<Vec<u32>, JsValue> {
+    init_logger();
+    let qr_config = config.map(|c| convert_config(&c)).transpose().map_err(to_js_error)?;
+    let result = render_qr_code_svg(url, qr_config.as_ref())
+        .map_err(to_js_error)?;
+    Ok(vec![result.width, result.height])
+}
+
+/// Returns the QR version (1-40) chosen for a given URL and config
+#[wasm_bindgen]
+pub fn get_qr_version(url: &str, config: Option<QrConfig>) -> Result<u8, JsValue> {
+    init_logger();
+    let qr_config = config.map(|c| convert_config(&c)).transpose().map_err(to_js_error)?;
+    let result = render_qr_code_svg(url, qr_config.as_ref())
+        .map_err(to_js_error)?;
+    Ok(result.version)
+}
+
+/// Returns the ISO/IEC 18004 penalty score of each of the 8 mask patterns, indexed by mask number
+#[wasm_bindgen]
+pub fn get_qr_mask_penalties(url: &str, config: Option<QrConfig>) -> Result<Vec<u32>, JsValue> {
+    init_logger();
+    let qr_config = config.map(|c| convert_config(&c)).transpose().map_err(to_js_error)?;
+    let result = render_qr_code_svg(url, qr_config.as_ref())
+        .map_err(to_js_error)?;
+    Ok(result.mask_penalties.to_vec())
+}
+
+/// A contrast problem found in a config
+#[wasm_bindgen]
+pub struct ContrastWarning {
+    severity: String,
+    element: String,
+    message: String,
+}
+
+#[wasm_bindgen]
+impl ContrastWarning {
+    /// "warning" when the code may be hard to scan, or "error" when it is unlikely to scan at all
+    #[wasm_bindgen(getter)]
+    pub fn severity(&self) -> String {
+        self.severity.clone()
+    }
+
+    /// The part of the code the colour belongs to, such as "data", "finder frame" or "background"
+    #[wasm_bindgen(getter)]
+    pub fn element(&self) -> String {
+        self.element.clone()
+    }
+
+    /// Human-readable description of the problem
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+/// Checks that the module colours of a config stand out from its background, returning
+/// every warning and error found, or none when the colours are fine
+#[wasm_bindgen]
+pub fn check_contrast(config: &QrConfig) -> Result<Vec<ContrastWarning>, JsValue> {
+    init_logger();
+    let qr_config = convert_config(config).map_err(to_js_error)?;
+    Ok(qr_config
+        .check_contrast()
+        .into_iter()
+        .map(|issue| ContrastWarning {
+            severity: match issue.severity {
+                ContrastSeverity::Warning => "warning",
+                ContrastSeverity::Error => "error",
+            }
+            .to_string(),
+            element: issue.element.to_string(),
+            message: issue.to_string(),
+        })
+        .collect())
+}
+
+/// Whether a code decoded under one simulated degradation
+#[cfg(feature = "verify")]
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct ScannabilityScenario {
+    degradation: String,
+    severity: f32,
+    decoded: bool,
+}
+
+#[cfg(feature = "verify")]
+#[wasm_bindgen]
+impl ScannabilityScenario {
+    /// "blur", "noise", "skew", "downscale" or "occlusion"
+    #[wasm_bindgen(getter)]
+    pub fn degradation(&self) -> String {
+        self.degradation.clone()
+    }
+
+    /// Strength of the degradation: module widths of blur, grey levels of noise, the fraction
+    /// the top and right edges shrink by, pixels per module, or the fraction of the image covered
+    #[wasm_bindgen(getter)]
+    pub fn severity(&self) -> f32 {
+        self.severity
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn decoded(&self) -> bool {
+        self.decoded
+    }
+}
+
+/// How well a rendered code survives simulated real-world conditions
+#[cfg(feature = "verify")]
+#[wasm_bindgen]
+pub struct ScannabilityReport {
+    score: u8,
+    scenarios: Vec<ScannabilityScenario>,
+}
+
+#[cfg(feature = "verify")]
+#[wasm_bindgen]
+impl ScannabilityReport {
+    /// Share of scenarios that decoded, from 0 to 100
+    #[wasm_bindgen(getter)]
+    pub fn score(&self) -> u8 {
+        self.score
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn scenarios(&self) -> Vec<ScannabilityScenario> {
+        self.scenarios.clone()
+    }
+}
+
+/// Renders a QR code, damages it with blur, noise, skew, downscaling and occlusion at
+/// increasing severities, and reports how many of the damaged codes still decode
+#[cfg(feature = "verify")]
+#[wasm_bindgen]
+pub fn get_qr_scannability(url: &str, config: Option<QrConfig>) -> Result<ScannabilityReport, JsValue> {
+    init_logger();
+    let qr_config = config.map(|c| convert_config(&c)).transpose().map_err(to_js_error)?;
+    let result = render_qr_code_svg(url, qr_config.as_ref())
+        .map_err(to_js_error)?;
+    let report = result.scannability(url).map_err(to_js_error)?;
+    Ok(ScannabilityReport {
+        score: report.score,
+        scenarios: report
+            .scenarios
+            .into_iter()
+            .map(|scenario| ScannabilityScenario {
+                degradation: scenario.degradation.to_string(),
+                severity: scenario.severity,
+                decoded: scenario.decoded,
+            })
+            .collect(),
+    })
+}
+
+/// Codeword errors a decoder reads back from a rendered code, per Reed-Solomon block
+#[cfg(feature = "verify")]
+#[wasm_bindgen]
+pub struct ErrorBudget {
+    errors: Vec<u32>,
+    correctable: Vec<u32>,
+    data_codewords: Vec<u32>,
+    ec_codewords: Vec<u32>,
+    recoverable: bool,
+    heatmap_svg: String,
+}
+
+#[cfg(feature = "verify")]
+#[wasm_bindgen]
+impl ErrorBudget {
+    /// Erroneous codewords of each block, in interleaving order
+    #[wasm_bindgen(getter)]
+    pub fn errors(&self) -> Vec<u32> {
+        self.errors.clone()
+    }
+
+    /// Most erroneous codewords each block can correct, in the order of `errors`
+    #[wasm_bindgen(getter)]
+    pub fn correctable(&self) -> Vec<u32> {
+        self.correctable.clone()
+    }
+
+    /// Data codewords of each block, in the order of `errors`
+    #[wasm_bindgen(getter)]
+    pub fn data_codewords(&self) -> Vec<u32> {
+        self.data_codewords.clone()
+    }
+
+    /// Error correction codewords of each block, in the order of `errors`
+    #[wasm_bindgen(getter)]
+    pub fn ec_codewords(&self) -> Vec<u32> {
+        self.ec_codewords.clone()
+    }
+
+    /// Whether every block is within its budget
+    #[wasm_bindgen(getter)]
+    pub fn recoverable(&self) -> bool {
+        self.recoverable
+    }
+
+    /// SVG the size of the rendered code highlighting every misread module, to lay over it
+    #[wasm_bindgen(getter)]
+    pub fn heatmap_svg(&self) -> String {
+        self.heatmap_svg.clone()
+    }
+}
+
+/// Renders a QR code, samples its modules back as a decoder would, and counts the codeword
+/// errors of every Reed-Solomon block against what its error correction level can correct
+#[cfg(feature = "verify")]
+#[wasm_bindgen]
+pub fn get_qr_error_budget(url: &str, config: Option<QrConfig>) -> Result<ErrorBudget, JsValue> {
     init_logger();
-    let qr_config = config.map(|c| convert_config(&c));
-    let result = render_qr_code_svg(url, qr_config.as_ref());
-    vec![result.width, result.height]
+    let qr_config = config.map(|c| convert_config(&c)).transpose().map_err(to_js_error)?;
+    let encoding = qr_config.as_ref().map(|c| c.encoding.clone()).unwrap_or_default();
+    let matrix = generate_qr_matrix(url, &encoding)
+        .map_err(to_js_error)?;
+    let result = render_qr_code_svg(url, qr_config.as_ref())
+        .map_err(to_js_error)?;
+    let report = result.error_budget(&matrix).map_err(to_js_error)?;
+    let per_block = |field: fn(&qrcode_core::BlockErrors) -> usize| report.blocks.iter().map(|block| field(block) as u32).collect();
+    Ok(ErrorBudget {
+        errors: per_block(|block| block.errors),
+        correctable: per_block(|block| block.correctable),
+        data_codewords: per_block(|block| block.data_codewords),
+        ec_codewords: per_block(|block| block.ec_codewords),
+        recoverable: report.is_recoverable(),
+        heatmap_svg: report.heatmap_svg(),
+    })
 }
@@ -0,0 +1,118 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+use crate::error::QrError;
+
+/// An sRGB color with an alpha channel
+///
+/// It is always written back out in a canonical form, so nothing but a color can reach the SVG.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    /// Opacity, from 0 (transparent) to 255 (opaque)
+    pub alpha: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+
+    /// Creates an opaque color
+    pub const fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Color { red, green, blue, alpha: u8::MAX }
+    }
+
+    /// Parses a CSS color, failing with `QrError::InvalidConfig` for anything else
+    ///
+    /// Accepts named colors, `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`, `rgba()`,
+    /// `hsl()` and `hsla()`. Every `color` constructor of the styles parses the same way.
+    pub fn parse(text: &str) -> Result<Self, QrError> {
+        text.parse()
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        self.alpha == u8::MAX
+    }
+}
+
+impl FromStr for Color {
+    type Err = QrError;
+
+    fn from_str(text: &str) -> Result<Self, QrError> {
+        let color: svgtypes::Color = text
+            .parse()
+            .map_err(|_| QrError::InvalidConfig(format!("invalid color \"{text}\"")))?;
+        Ok(Color { red: color.red, green: color.green, blue: color.blue, alpha: color.alpha })
+    }
+}
+
+impl fmt::Display for Color {
+    /// Writes `#rrggbb` for opaque colors and `rgba()` for translucent ones, which every SVG reader understands
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Color { red, green, blue, alpha } = *self;
+        if self.is_opaque() {
+            write!(f, "#{red:02x}{green:02x}{blue:02x}")
+        } else {
+            let opacity = (f32::from(alpha) / 255.0 * 1000.0).round() / 1000.0;
+            write!(f, "rgba({red}, {green}, {blue}, {opacity})")
+        }
+    }
+}
+
+/// Escapes text for use inside an XML attribute value or element, whichever quotes delimit it
+pub(crate) fn escape_xml(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 16);
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(character),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_css_color_syntaxes() {
+        let olive = Color::rgb(128, 128, 0);
+        for text in ["olive", "#808000", "#808000ff", "rgb(128, 128, 0)", "rgb(50.2%, 50.2%, 0%)", "hsl(60, 100%, 25.1%)", " olive "] {
+            assert_eq!(Color::parse(text), Ok(olive), "{text}");
+        }
+        assert_eq!(Color::parse("#fc0"), Ok(Color::rgb(255, 204, 0)));
+        assert_eq!(Color::parse("rgba(0, 0, 255, 0.5)").unwrap().alpha, 127);
+        assert_eq!(Color::parse("#0000ff80").unwrap().alpha, 128);
+    }
+
+    #[test]
+    fn refuses_anything_but_a_color() {
+        for text in ["", "reddish", "#12345", "#ggg", "red' onload='alert(1)", "red\" onload=\"alert(1)", "rgb(1, 2)", "url(#x)"] {
+            assert!(matches!(Color::parse(text), Err(QrError::InvalidConfig(_))), "{text}");
+        }
+    }
+
+    #[test]
+    fn writes_canonical_colors() {
+        assert_eq!(Color::parse("Red").unwrap().to_string(), "#ff0000");
+        assert_eq!(Color::parse("hsl(0, 100%, 50%)").unwrap().to_string(), "#ff0000");
+        assert_eq!(Color::parse("rgba(0, 0, 255, 0.5)").unwrap().to_string(), "rgba(0, 0, 255, 0.498)");
+        assert_eq!(Color::parse("transparent").unwrap().to_string(), "rgba(0, 0, 0, 0)");
+    }
+
+    #[test]
+    fn escapes_markup() {
+        assert_eq!(escape_xml("#ff0000"), "#ff0000");
+        assert_eq!(escape_xml(r#"a'b"c<d>e&f"#), "a&apos;b&quot;c&lt;d&gt;e&amp;f");
+    }
+}
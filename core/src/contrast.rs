@@ -0,0 +1,285 @@
+use std::fmt;
+use crate::color::Color;
+use crate::gradient::Gradient;
+use crate::svg_rendering::{AlignmentStyle, Background, DataStyle, FinderStyle, QrRenderConfig};
+
+/// Contrast ratio below which scanners are unlikely to tell dark modules from light ones
+pub const MIN_CONTRAST_RATIO: f32 = 2.0;
+/// Contrast ratio below which codes start failing on dim screens, glossy print or cheap cameras
+pub const RECOMMENDED_CONTRAST_RATIO: f32 = 3.0;
+
+/// How serious a contrast issue is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContrastSeverity {
+    /// The code may be hard to scan for some readers or in poor conditions
+    Warning,
+    /// The code is unlikely to scan at all
+    Error,
+}
+
+/// What is wrong with a color of the configuration
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContrastProblem {
+    /// The contrast ratio between a module color and a background color is too low.
+    /// Ratios run from 1 (identical luminance) to 21 (black on white).
+    LowContrast { color: Color, background: Color, ratio: f32 },
+    /// The modules are lighter than the background, or darker in an inverted code, so readers
+    /// that expect the configured polarity see the code inverted
+    InvertedPolarity { color: Color, background: Color },
+}
+
+/// A problem found by `QrRenderConfig::check_contrast`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContrastIssue {
+    pub severity: ContrastSeverity,
+    /// Part of the code the color belongs to: "data", "finder frame", "finder eye" or "alignment"
+    pub element: &'static str,
+    pub problem: ContrastProblem,
+}
+
+impl fmt::Display for ContrastIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let element = self.element;
+        match &self.problem {
+            ContrastProblem::LowContrast { color, background, ratio } => {
+                let required = match self.severity {
+                    ContrastSeverity::Error => MIN_CONTRAST_RATIO,
+                    ContrastSeverity::Warning => RECOMMENDED_CONTRAST_RATIO,
+                };
+                write!(
+                    f,
+                    "{element} color {color} has a contrast ratio of {ratio:.2}:1 against {background}, below {required}:1"
+                )
+            }
+            ContrastProblem::InvertedPolarity { color, background } => write!(
+                f,
+                "{element} color {color} against {background} draws the code with the opposite polarity to \
+                 its `inverted` setting, which many readers cannot scan"
+            ),
+        }
+    }
+}
+
+/// Relative luminance as defined by WCAG 2, from 0 (black) to 1 (white), ignoring alpha
+fn luminance(color: Color) -> f32 {
+    let linear = |channel: u8| {
+        let value = f32::from(channel) / 255.0;
+        if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(color.red) + 0.7152 * linear(color.green) + 0.0722 * linear(color.blue)
+}
+
+/// Composites a possibly translucent color over an opaque one
+fn over(color: Color, under: Color) -> Color {
+    let alpha = u32::from(color.alpha);
+    let mix = |top: u8, bottom: u8| ((u32::from(top) * alpha + u32::from(bottom) * (255 - alpha) + 127) / 255) as u8;
+    Color::rgb(mix(color.red, under.red), mix(color.green, under.green), mix(color.blue, under.blue))
+}
+
+/// Ratio between the lighter and darker of two luminances
+fn contrast_ratio(a: f32, b: f32) -> f32 {
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// Colors a gradient can show at any point, which are its stops
+fn stop_colors(gradient: &Gradient) -> Vec<Color> {
+    let (Gradient::Linear { stops, .. } | Gradient::Radial { stops, .. }) = gradient;
+    stops.iter().map(|stop| stop.color).collect()
+}
+
+impl DataStyle {
+    fn colors(&self) -> Vec<Color> {
+        match self {
+            DataStyle::Color(color) => vec![*color],
+            DataStyle::Gradient(gradient) => stop_colors(gradient),
+        }
+    }
+}
+
+impl FinderStyle {
+    fn colors(&self) -> Vec<Color> {
+        match self {
+            FinderStyle::Color(color) => vec![*color],
+            FinderStyle::Gradient(gradient) => stop_colors(gradient),
+        }
+    }
+}
+
+impl QrRenderConfig {
+    /// Checks that the module colors stand out from the background before rendering
+    ///
+    /// Every data, finder and alignment color, gradient stops included, is compared with every
+    /// background color using the WCAG contrast ratio. Ratios below `MIN_CONTRAST_RATIO` are
+    /// errors and ratios below `RECOMMENDED_CONTRAST_RATIO` warnings, reported once per element
+    /// for its worst pairing. Modules lighter than the background are reported as inverted
+    /// polarity unless `inverted` is set, in which case modules darker than it are. Translucent
+    /// colors are judged as composited over what is behind them. A transparent background is
+    /// judged by the matte JPEG output shows behind it, and an image background is not checked.
+    pub fn check_contrast(&self) -> Vec<ContrastIssue> {
+        let mut issues = Vec::new();
+        let matte = over(self.matte, Color::WHITE);
+        let backgrounds: Vec<Color> = match &self.background {
+            Background::None => vec![matte],
+            Background::Color(color) => vec![over(*color, matte)],
+            Background::Gradient(gradient) => stop_colors(gradient).into_iter().map(|color| over(color, matte)).collect(),
+            Background::Image(_) => Vec::new(),
+        };
+
+        // An inverted code cuts every module out of the data color, leaving the finder and alignment styles unused
+        let mut elements = vec![("data", self.data_styling.colors())];
+        if !self.inverted {
+            let AlignmentStyle::Color(alignment) = self.alignment_styling;
+            elements.push(("finder frame", self.finder_frame_styling.colors()));
+            elements.push(("finder eye", self.finder_eye_styling.colors()));
+            elements.push(("alignment", vec![alignment]));
+        }
+
+        for (element, colors) in elements {
+            let mut worst: Option<(f32, Color, Color)> = None;
+            let mut reversed = None;
+            for &color in &colors {
+                for &background in &backgrounds {
+                    let (module, ground) = (luminance(over(color, background)), luminance(background));
+                    let ratio = contrast_ratio(module, ground);
+                    if worst.is_none_or(|(lowest, _, _)| ratio < lowest) {
+                        worst = Some((ratio, color, background));
+                    }
+                    // Inverted codes paint the data color around modules that show the background
+                    if reversed.is_none() && module > ground {
+                        reversed = Some((color, background));
+                    }
+                }
+            }
+            if let Some((ratio, color, background)) = worst {
+                let severity = if ratio < MIN_CONTRAST_RATIO {
+                    Some(ContrastSeverity::Error)
+                } else if ratio < RECOMMENDED_CONTRAST_RATIO {
+                    Some(ContrastSeverity::Warning)
+                } else {
+                    None
+                };
+                if let Some(severity) = severity {
+                    issues.push(ContrastIssue {
+                        severity,
+                        element,
+                        problem: ContrastProblem::LowContrast { color, background, ratio },
+                    });
+                }
+            }
+            if let Some((color, background)) = reversed {
+                issues.push(ContrastIssue {
+                    severity: ContrastSeverity::Warning,
+                    element,
+                    problem: ContrastProblem::InvertedPolarity { color, background },
+                });
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GradientStop, LogoImage};
+
+    // Draws every module in `modules`
+    fn config(modules: &str, background: &str) -> QrRenderConfig {
+        QrRenderConfig {
+            data_styling: DataStyle::color(modules).unwrap(),
+            finder_frame_styling: FinderStyle::color(modules).unwrap(),
+            finder_eye_styling: FinderStyle::color(modules).unwrap(),
+            alignment_styling: AlignmentStyle::color(modules).unwrap(),
+            background: Background::color(background).unwrap(),
+            ..QrRenderConfig::default()
+        }
+    }
+
+    #[test]
+    fn accepts_the_default_config() {
+        assert_eq!(QrRenderConfig::default().check_contrast(), Vec::new());
+    }
+
+    #[test]
+    fn refuses_yellow_on_white() {
+        let config = QrRenderConfig {
+            data_styling: DataStyle::color("yellow").unwrap(),
+            ..QrRenderConfig::default()
+        };
+        let issues = config.check_contrast();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, ContrastSeverity::Error);
+        assert_eq!(issues[0].element, "data");
+        let ContrastProblem::LowContrast { ratio, .. } = issues[0].problem else {
+            panic!("expected low contrast, got {:?}", issues[0]);
+        };
+        assert!((ratio - 1.07).abs() < 0.01, "{ratio}");
+        assert_eq!(
+            issues[0].to_string(),
+            "data color #ffff00 has a contrast ratio of 1.07:1 against #ffffff, below 2:1"
+        );
+    }
+
+    #[test]
+    fn judges_colors_however_they_are_written() {
+        // Light grey is a warning for every element whichever way it is written
+        for grey in ["darkgrey", "#aaa", "#AAAAAA", "rgb(170, 170, 170)", "rgb(67%, 67%, 67%)", "hsl(0, 0%, 67%)"] {
+            let issues = config(grey, "white").check_contrast();
+            assert_eq!(issues.len(), 4, "{grey}: {issues:?}");
+            assert!(issues.iter().all(|issue| issue.severity == ContrastSeverity::Warning), "{grey}");
+        }
+        let issues = config("hsl(120, 100%, 20%)", "hsl(60, 100%, 97%)").check_contrast();
+        assert_eq!(issues, Vec::new());
+    }
+
+    #[test]
+    fn reports_the_polarity_of_light_modules() {
+        let issues = config("white", "#111").check_contrast();
+        assert_eq!(issues.len(), 4, "{issues:?}");
+        assert!(issues.iter().all(|issue| issue.severity == ContrastSeverity::Warning));
+        assert!(matches!(issues[0].problem, ContrastProblem::InvertedPolarity { .. }));
+
+        // An inverted code fills the ground with the data color, so it must be the darker one
+        let inverted = QrRenderConfig { inverted: true, ..config("#111", "white") };
+        assert_eq!(inverted.check_contrast(), Vec::new());
+        let inverted = QrRenderConfig { inverted: true, ..config("white", "#111") };
+        assert!(matches!(inverted.check_contrast()[0].problem, ContrastProblem::InvertedPolarity { .. }));
+    }
+
+    #[test]
+    fn checks_every_gradient_stop_and_translucent_colors() {
+        let stops = vec![GradientStop::new(0.0, "black".parse().unwrap()), GradientStop::new(1.0, "#ddd".parse().unwrap())];
+        let config = QrRenderConfig {
+            data_styling: DataStyle::Gradient(Gradient::Linear { angle: 0.0, stops }),
+            ..QrRenderConfig::default()
+        };
+        let issues = config.check_contrast();
+        assert!(matches!(&issues[0].problem, ContrastProblem::LowContrast { color, .. } if color.to_string() == "#dddddd"));
+
+        // Mostly transparent black over white is light grey
+        let issues = self::config("rgba(0, 0, 0, 0.3)", "white").check_contrast();
+        assert_eq!(issues[0].severity, ContrastSeverity::Warning, "{issues:?}");
+    }
+
+    #[test]
+    fn judges_transparent_backgrounds_by_the_matte() {
+        let transparent = QrRenderConfig {
+            background: Background::None,
+            matte: "#222".parse().unwrap(),
+            ..config("black", "white")
+        };
+        let issues = transparent.check_contrast();
+        assert_eq!(issues.len(), 4, "{issues:?}");
+        assert!(issues.iter().all(|issue| issue.severity == ContrastSeverity::Error), "{issues:?}");
+
+        let image = QrRenderConfig {
+            background: Background::Image(LogoImage::Raster(Vec::new())),
+            ..config("white", "white")
+        };
+        assert_eq!(image.check_contrast(), Vec::new());
+    }
+}
@@ -0,0 +1,268 @@
+use crate::ec_blocks::{codeword_blocks, correctable_errors_per_block, rs_blocks};
+use crate::encoding::ErrorCorrectionLevel;
+use crate::error::QrError;
+use crate::qr_matrix::{data_module_positions, mask_applies};
+use crate::reed_solomon;
+
+/// Modules sampled from an image, before any error correction
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SampledGrid {
+    pub width: usize,
+    /// Whether each module was read as dark, in row-major order
+    pub modules: Vec<bool>,
+}
+
+impl SampledGrid {
+    pub(crate) fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.width + x]
+    }
+
+    /// QR code version a symbol of this width has
+    pub(crate) fn version(&self) -> u8 {
+        ((self.width - 17) / 4) as u8
+    }
+
+    /// Reads `coordinates` as a binary number, most significant bit first
+    fn read_bits(&self, coordinates: impl Iterator<Item = (usize, usize)>) -> u32 {
+        coordinates.fold(0, |bits, (x, y)| bits << 1 | u32::from(self.is_dark(x, y)))
+    }
+
+    /// Number of timing pattern modules read with the wrong color
+    pub(crate) fn timing_errors(&self) -> usize {
+        (8..self.width - 8)
+            .filter(|&i| self.is_dark(i, 6) != (i % 2 == 0))
+            .chain((8..self.width - 8).filter(|&i| self.is_dark(6, i) != (i % 2 == 0)))
+            .count()
+    }
+
+    /// Reads the error correction level and mask from the nearest valid of the two format
+    /// information copies, with the number of bits that had to be corrected
+    pub(crate) fn format(&self) -> Option<(ErrorCorrectionLevel, u8, u32)> {
+        let width = self.width;
+        let beside_top_left = [(0, 8), (1, 8), (2, 8), (3, 8), (4, 8), (5, 8), (7, 8), (8, 8), (8, 7)]
+            .into_iter()
+            .chain((0..6).rev().map(|y| (8, y)));
+        let split = (1..8).map(|i| (8, width - i)).chain((1..9).rev().map(|i| (width - i, 8)));
+        let copies = [self.read_bits(beside_top_left), self.read_bits(split)];
+        let (data, distance) = (0..32u32)
+            .map(|data| {
+                let codeword = format_codeword(data);
+                (data, copies.iter().map(|copy| (copy ^ codeword).count_ones()).min().unwrap_or(15))
+            })
+            .min_by_key(|&(_, distance)| distance)?;
+        if distance > 3 {
+            return None;
+        }
+        let level = match data >> 3 {
+            0b01 => ErrorCorrectionLevel::Low,
+            0b00 => ErrorCorrectionLevel::Medium,
+            0b11 => ErrorCorrectionLevel::Quartile,
+            _ => ErrorCorrectionLevel::High,
+        };
+        Some((level, (data & 0b111) as u8, distance))
+    }
+
+    /// Reads the version from the version information beside the top right finder pattern,
+    /// which only symbols of version 7 and up carry
+    pub(crate) fn encoded_version(&self) -> Option<u8> {
+        let width = self.width;
+        let bits = self.read_bits((0..6).rev().flat_map(|y| (9..12).map(move |i| (width - i, y))));
+        (7..=40u32)
+            .find(|&version| (bits ^ (version << 12 | bch_remainder(version << 12, 0x1f25))).count_ones() <= 3)
+            .map(|version| version as u8)
+    }
+
+    /// Unmasks, error corrects and parses the data of the symbol
+    pub(crate) fn decode(&self) -> Result<String, QrError> {
+        let (level, mask, _) = self
+            .format()
+            .ok_or_else(|| QrError::Verification("found a QR code but could not read its format".to_string()))?;
+        let version = self.version();
+        let positions = data_module_positions(version);
+        let codewords: Vec<u8> = positions
+            .chunks_exact(8)
+            .map(|bits| {
+                bits.iter()
+                    .fold(0, |codeword, &(x, y)| codeword << 1 | u8::from(self.is_dark(x, y) ^ mask_applies(mask, x, y)))
+            })
+            .collect();
+
+        let blocks = rs_blocks(version, level);
+        let mut deinterleaved = vec![Vec::new(); blocks.len()];
+        for (&codeword, &block) in codewords.iter().zip(&codeword_blocks(&blocks)) {
+            deinterleaved[block].push(codeword);
+        }
+        let correctable = correctable_errors_per_block(version, level);
+        let mut data = Vec::new();
+        for (codewords, block) in deinterleaved.iter_mut().zip(&blocks) {
+            reed_solomon::correct(codewords, block.ec_codewords, correctable)
+                .map_err(|e| QrError::Verification(format!("found a QR code but could not decode it: {e}")))?;
+            data.extend_from_slice(&codewords[..block.data_codewords]);
+        }
+        parse_segments(&data, version)
+    }
+}
+
+/// Remainder of `value` divided by the BCH `generator` polynomial
+fn bch_remainder(mut value: u32, generator: u32) -> u32 {
+    let degree = 31 - generator.leading_zeros();
+    while value >> degree != 0 {
+        value ^= generator << (31 - value.leading_zeros() - degree);
+    }
+    value
+}
+
+/// The masked 15 bit format information carrying five bits of `data`
+fn format_codeword(data: u32) -> u32 {
+    (data << 10 | bch_remainder(data << 10, 0x537)) ^ 0x5412
+}
+
+/// Reads bits most significant first from a run of codewords
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.position
+    }
+
+    fn read(&mut self, count: usize) -> Result<u32, QrError> {
+        if count > self.remaining() {
+            return Err(QrError::Verification("decoded data ends in the middle of a segment".to_string()));
+        }
+        let bits = (self.position..self.position + count)
+            .fold(0, |bits, i| bits << 1 | u32::from(self.data[i / 8] >> (7 - i % 8) & 1));
+        self.position += count;
+        Ok(bits)
+    }
+}
+
+const ALPHANUMERIC: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// Parses the segments of the corrected data codewords back into text
+///
+/// Numeric, alphanumeric, byte and Kanji segments are joined into one byte string, with
+/// Kanji turned back into its Shift JIS bytes, and read as UTF-8 like the encoder wrote it.
+fn parse_segments(data: &[u8], version: u8) -> Result<String, QrError> {
+    let mut reader = BitReader { data, position: 0 };
+    // Count field sizes of the numeric, alphanumeric, byte and Kanji modes
+    let count_bits = match version {
+        1..=9 => [10, 9, 8, 8],
+        10..=26 => [12, 11, 16, 10],
+        _ => [14, 13, 16, 12],
+    };
+    let mut bytes = Vec::new();
+    while reader.remaining() >= 4 {
+        match reader.read(4)? {
+            0b0000 => break,
+            0b0001 => {
+                let mut count = reader.read(count_bits[0])? as usize;
+                while count > 0 {
+                    let digits = count.min(3);
+                    let value = reader.read([4, 7, 10][digits - 1])?;
+                    let text = format!("{value:0digits$}");
+                    if text.len() != digits {
+                        return Err(QrError::Verification("decoded an invalid numeric segment".to_string()));
+                    }
+                    bytes.extend_from_slice(text.as_bytes());
+                    count -= digits;
+                }
+            }
+            0b0010 => {
+                let mut count = reader.read(count_bits[1])? as usize;
+                while count > 0 {
+                    let characters = count.min(2);
+                    let value = reader.read([6, 11][characters - 1])? as usize;
+                    let (first, second) = if characters == 2 { (value / 45, Some(value % 45)) } else { (value, None) };
+                    for index in std::iter::once(first).chain(second) {
+                        let &character = ALPHANUMERIC
+                            .get(index)
+                            .ok_or_else(|| QrError::Verification("decoded an invalid alphanumeric segment".to_string()))?;
+                        bytes.push(character);
+                    }
+                    count -= characters;
+                }
+            }
+            0b0100 => {
+                let count = reader.read(count_bits[2])?;
+                for _ in 0..count {
+                    bytes.push(reader.read(8)? as u8);
+                }
+            }
+            0b1000 => {
+                let count = reader.read(count_bits[3])?;
+                for _ in 0..count {
+                    let value = reader.read(13)?;
+                    let packed = ((value / 0xc0) << 8) | (value % 0xc0);
+                    let shift_jis = if packed + 0x8140 <= 0x9ffc { packed + 0x8140 } else { packed + 0xc140 };
+                    bytes.extend_from_slice(&(shift_jis as u16).to_be_bytes());
+                }
+            }
+            0b0111 => {
+                // ECI designators only name a character set; the bytes that follow are kept as they are
+                let designator = reader.read(8)?;
+                if designator & 0x80 != 0 {
+                    reader.read(if designator & 0x40 == 0 { 8 } else { 16 })?;
+                }
+            }
+            mode => {
+                return Err(QrError::Verification(format!("decoded an unsupported segment mode {mode:04b}")));
+            }
+        }
+    }
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::{encode_qr_code, QrEncodeOptions, VersionConstraint};
+
+    fn grid(data: &str, options: &QrEncodeOptions) -> SampledGrid {
+        let code = encode_qr_code(data, options).unwrap();
+        SampledGrid { width: code.width, modules: code.modules }
+    }
+
+    #[test]
+    fn decodes_every_segment_mode() {
+        // Digits, uppercase, lowercase and kanji make the encoder mix all four modes
+        let data = "0123456789 HTTPS://JEDI.ORG/ https://jedi.org/?q=42 点茗";
+        for level in [ErrorCorrectionLevel::Low, ErrorCorrectionLevel::High] {
+            for version in [8, 12, 30] {
+                let options = QrEncodeOptions {
+                    error_correction: level,
+                    version: VersionConstraint::Fixed(version),
+                    ..QrEncodeOptions::default()
+                };
+                let grid = grid(data, &options);
+                assert_eq!(grid.decode(), Ok(data.to_string()), "version {version} {level:?}");
+                assert_eq!(grid.format().map(|(decoded, _, distance)| (decoded, distance)), Some((level, 0)));
+                assert_eq!(grid.encoded_version(), Some(version).filter(|&version| version >= 7));
+                assert_eq!(grid.timing_errors(), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn corrects_misread_modules() {
+        let options = QrEncodeOptions { error_correction: ErrorCorrectionLevel::Quartile, ..QrEncodeOptions::default() };
+        let mut grid = grid("https://jedi.org/decode", &options);
+        let width = grid.width;
+        // A scuff across the middle and a flipped format bit
+        for x in 10..16 {
+            grid.modules[12 * width + x] = !grid.modules[12 * width + x];
+        }
+        grid.modules[8 * width + 2] = !grid.modules[8 * width + 2];
+        assert_eq!(grid.decode(), Ok("https://jedi.org/decode".to_string()));
+
+        // Inverting a whole quadrant is beyond any error correction
+        for y in width / 2..width {
+            for x in width / 2..width {
+                grid.modules[y * width + x] = !grid.modules[y * width + x];
+            }
+        }
+        assert!(matches!(grid.decode(), Err(QrError::Verification(_))));
+    }
+}
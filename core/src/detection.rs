@@ -0,0 +1,384 @@
+// Locating QR code symbols in an image and sampling their module grids
+//
+// The image is thresholded and split into connected components. Finder and alignment
+// patterns are found as light holes holding a dark eye, whatever the shape of the frame
+// and the eye, so styled patterns (dots, rounded squares, triangles) are found as well as
+// square ones. Three finder patterns, and the bottom right alignment pattern when there is
+// one, fix the perspective the grid is sampled through.
+
+use std::collections::HashSet;
+use image::GrayImage;
+use crate::decoding::SampledGrid;
+use crate::error::QrError;
+use crate::perspective::Perspective;
+
+/// Most grids `Detection::grids` samples, best scoring first, before giving up
+const MAX_GRIDS: usize = 8;
+
+/// Most finder pattern triples considered per image
+const MAX_TRIPLES: usize = 3;
+
+/// A light or dark connected component of the thresholded image
+#[derive(Debug, Clone, Copy)]
+struct Component {
+    dark: bool,
+    area: usize,
+    min: (usize, usize),
+    max: (usize, usize),
+    sum: (f64, f64),
+    touches_border: bool,
+}
+
+impl Component {
+    fn size(&self) -> (f64, f64) {
+        ((self.max.0 - self.min.0 + 1) as f64, (self.max.1 - self.min.1 + 1) as f64)
+    }
+
+    fn inside(&self, other: &Component) -> bool {
+        self.min.0 >= other.min.0 && self.min.1 >= other.min.1 && self.max.0 <= other.max.0 && self.max.1 <= other.max.1
+    }
+}
+
+/// A finder or alignment pattern candidate
+#[derive(Debug, Clone, Copy)]
+struct Pattern {
+    centre: (f64, f64),
+    /// Estimated module size in pixels
+    module: f64,
+}
+
+/// A perspective the grid of a given version might be sampled through
+struct Candidate {
+    version: u8,
+    transform: Perspective,
+    score: f64,
+}
+
+/// A thresholded image and the candidate grids found in it
+pub(crate) struct Detection {
+    width: usize,
+    height: usize,
+    dark: Vec<bool>,
+    candidates: Vec<Candidate>,
+}
+
+impl Detection {
+    /// Thresholds a dark on light greyscale image and locates its finder patterns
+    ///
+    /// Fails with `QrError::Verification` when no three patterns line up as a symbol's.
+    pub(crate) fn new(image: &GrayImage) -> Result<Self, QrError> {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let threshold = otsu_threshold(image);
+        let dark: Vec<bool> = image.pixels().map(|pixel| pixel.0[0] <= threshold).collect();
+        let mut detection = Detection { width, height, dark, candidates: Vec::new() };
+
+        let (finders, alignments) = detection.patterns();
+        for (top_left, top_right, bottom_left) in finder_triples(&finders) {
+            detection.add_candidates(top_left, top_right, bottom_left, &alignments);
+        }
+        if detection.candidates.is_empty() {
+            return Err(QrError::Verification("no QR code found in the rendered image".to_string()));
+        }
+        detection.candidates.sort_by(|a, b| a.score.total_cmp(&b.score));
+        Ok(detection)
+    }
+
+    /// Samples the candidate grids, most plausible first
+    pub(crate) fn grids(&self) -> impl Iterator<Item = SampledGrid> + '_ {
+        self.candidates
+            .iter()
+            .take(MAX_GRIDS)
+            .map(|candidate| self.sample(&candidate.transform, candidate.version, |_, _| true))
+    }
+
+    fn is_dark(&self, (x, y): (f64, f64)) -> bool {
+        let (x, y) = (x.floor(), y.floor());
+        x >= 0.0 && y >= 0.0 && (x as usize) < self.width && (y as usize) < self.height && self.dark[y as usize * self.width + x as usize]
+    }
+
+    /// Samples the modules `included` selects, leaving the rest light
+    ///
+    /// Each module is read at nine points around its centre, the centre counting twice,
+    /// and taken as dark when at least four of the ten votes are, so thin lines and small
+    /// dots still read as dark.
+    fn sample(&self, transform: &Perspective, version: u8, included: impl Fn(usize, usize) -> bool) -> SampledGrid {
+        let width = 17 + 4 * version as usize;
+        let modules = (0..width * width)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                if !included(x, y) {
+                    return false;
+                }
+                let votes: usize = [-0.2, 0.0, 0.2]
+                    .into_iter()
+                    .flat_map(|dy| [-0.2, 0.0, 0.2].map(move |dx| (dx, dy)))
+                    .map(|(dx, dy)| {
+                        let dark = self.is_dark(transform.map((x as f64 + 0.5 + dx, y as f64 + 0.5 + dy)));
+                        usize::from(dark) * if dx == 0.0 && dy == 0.0 { 2 } else { 1 }
+                    })
+                    .sum();
+                votes >= 4
+            })
+            .collect();
+        SampledGrid { width, modules }
+    }
+
+    /// Scores how well a grid fits the timing patterns and format and version information,
+    /// lower being better, and returns the version the version information names if any
+    fn score(&self, transform: &Perspective, version: u8) -> (f64, Option<u8>) {
+        let width = 17 + 4 * version as usize;
+        let grid = self.sample(transform, version, |x, y| {
+            x == 6 || y == 6 || x == 8 || y == 8 || (version >= 7 && y < 6 && x + 12 > width && x + 8 < width)
+        });
+        let timing = grid.timing_errors() as f64 / (2 * (width - 16)) as f64;
+        let format = grid.format().map_or(1.0, |(_, _, distance)| f64::from(distance) / 4.0);
+        let encoded = if version >= 7 { grid.encoded_version() } else { None };
+        let version_mismatch = if version >= 7 && encoded != Some(version) { 0.5 } else { 0.0 };
+        (timing + format + version_mismatch, encoded)
+    }
+
+    /// Adds the grids of every plausible version through the finder patterns
+    fn add_candidates(&mut self, top_left: Pattern, top_right: Pattern, bottom_left: Pattern, alignments: &[Pattern]) {
+        let (tl, tr, bl) = (top_left.centre, top_right.centre, bottom_left.centre);
+        let module = (top_left.module + top_right.module + bottom_left.module) / 3.0;
+        let leg = (distance(tl, tr) + distance(tl, bl)) / 2.0;
+        let estimate = ((leg / module + 7.0 - 17.0) / 4.0).round() as i32;
+        let mut versions: Vec<u8> = (estimate - 2..=estimate + 2).filter(|v| (1..=40).contains(v)).map(|v| v as u8).collect();
+
+        let mut tried = 0;
+        while tried < versions.len() {
+            let version = versions[tried];
+            tried += 1;
+            let far = (17 + 4 * version) as f64 - 3.5;
+            let finders = [(3.5, 3.5), (far, 3.5), (3.5, far)];
+            let Some(affine) = Perspective::between(
+                [finders[0], finders[1], (far, far), finders[2]],
+                [tl, tr, (tr.0 + bl.0 - tl.0, tr.1 + bl.1 - tl.1), bl],
+            ) else {
+                continue;
+            };
+            let mut transforms = vec![affine];
+
+            // The bottom right alignment pattern pins down the perspective the finders leave open
+            if version >= 2 {
+                let corner = far - 3.0;
+                let predicted = affine.map((corner, corner));
+                let mut nearby: Vec<&Pattern> = alignments
+                    .iter()
+                    .filter(|alignment| alignment.module > module / 2.0 && alignment.module < module * 2.0)
+                    .filter(|alignment| distance(alignment.centre, predicted) < leg / 4.0 + 4.0 * module)
+                    .collect();
+                nearby.sort_by(|a, b| distance(a.centre, predicted).total_cmp(&distance(b.centre, predicted)));
+                transforms.extend(nearby.iter().take(3).filter_map(|alignment| {
+                    Perspective::between([finders[0], finders[1], (corner, corner), finders[2]], [tl, tr, alignment.centre, bl])
+                }));
+            }
+
+            for transform in transforms {
+                let (score, encoded) = self.score(&transform, version);
+                if let Some(encoded) = encoded.filter(|encoded| !versions.contains(encoded)) {
+                    versions.push(encoded);
+                }
+                self.candidates.push(Candidate { version, transform, score });
+            }
+        }
+    }
+
+    /// Finds every finder and alignment pattern candidate: a light hole, not touching the
+    /// image border, with a dark eye in its middle
+    fn patterns(&self) -> (Vec<Pattern>, Vec<Pattern>) {
+        let (labels, components) = self.components();
+
+        // Dark components bordering each light one
+        let mut neighbours = HashSet::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let here = labels[y * self.width + x];
+                for (nx, ny) in [(x + 1, y), (x, y + 1)] {
+                    if nx < self.width && ny < self.height {
+                        let there = labels[ny * self.width + nx];
+                        if components[here].dark != components[there].dark {
+                            neighbours.insert(if components[here].dark { (there, here) } else { (here, there) });
+                        }
+                    }
+                }
+            }
+        }
+        let mut islands = vec![Vec::new(); components.len()];
+        for (light, dark) in neighbours {
+            if components[dark].inside(&components[light]) {
+                islands[light].push(dark);
+            }
+        }
+
+        let (mut finders, mut alignments) = (Vec::new(), Vec::new());
+        for (hole, islands) in components.iter().zip(&islands) {
+            if hole.dark || hole.touches_border || hole.area < 4 {
+                continue;
+            }
+            let Some(eye) = islands.iter().map(|&island| components[island]).max_by_key(|island| island.area) else {
+                continue;
+            };
+            let (hole_width, hole_height) = hole.size();
+            let (eye_width, eye_height) = eye.size();
+            if hole_width > 2.0 * hole_height || hole_height > 2.0 * hole_width {
+                continue;
+            }
+            // The eye sits in the middle of the hole
+            let offset = (
+                (eye.min.0 + eye.max.0) as f64 - (hole.min.0 + hole.max.0) as f64,
+                (eye.min.1 + eye.max.1) as f64 - (hole.min.1 + hole.max.1) as f64,
+            );
+            if offset.0.abs() > 0.4 * hole_width || offset.1.abs() > 0.4 * hole_height {
+                continue;
+            }
+
+            let (area, sum) = islands.iter().map(|&island| components[island]).fold(
+                (hole.area, hole.sum),
+                |(area, sum), island| (area + island.area, (sum.0 + island.sum.0, sum.1 + island.sum.1)),
+            );
+            let centre = (sum.0 / area as f64, sum.1 / area as f64);
+            let size = (area as f64).sqrt();
+            let ratio = (eye_width / hole_width + eye_height / hole_height) / 2.0;
+            // A finder's eye spans 3 of the 5 modules of its hole, an alignment's 1 of 3
+            if (0.42..0.8).contains(&ratio) {
+                finders.push(Pattern { centre, module: size / 5.0 });
+            } else if (0.15..0.42).contains(&ratio) {
+                alignments.push(Pattern { centre, module: size / 3.0 });
+            }
+        }
+        (finders, alignments)
+    }
+
+    /// Labels connected components, dark pixels joining diagonally and light ones only
+    /// orthogonally so a closed dark ring always separates its hole from the outside
+    fn components(&self) -> (Vec<usize>, Vec<Component>) {
+        let (width, height) = (self.width, self.height);
+        let mut parents: Vec<usize> = (0..width * height).collect();
+        for y in 0..height {
+            for x in 0..width {
+                let i = y * width + x;
+                let dark = self.dark[i];
+                let neighbours = [
+                    (x > 0).then(|| i - 1),
+                    (y > 0).then(|| i - width),
+                    (dark && y > 0 && x > 0).then(|| i - width - 1),
+                    (dark && y > 0 && x + 1 < width).then(|| i - width + 1),
+                ];
+                for neighbour in neighbours.into_iter().flatten() {
+                    if self.dark[neighbour] == dark {
+                        let (a, b) = (find(&mut parents, i), find(&mut parents, neighbour));
+                        parents[a.max(b)] = a.min(b);
+                    }
+                }
+            }
+        }
+
+        let mut indices = vec![usize::MAX; width * height];
+        let mut labels = vec![0; width * height];
+        let mut components: Vec<Component> = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let i = y * width + x;
+                let root = find(&mut parents, i);
+                if indices[root] == usize::MAX {
+                    indices[root] = components.len();
+                    components.push(Component {
+                        dark: self.dark[i],
+                        area: 0,
+                        min: (x, y),
+                        max: (x, y),
+                        sum: (0.0, 0.0),
+                        touches_border: false,
+                    });
+                }
+                let component = &mut components[indices[root]];
+                component.area += 1;
+                component.min = (component.min.0.min(x), component.min.1.min(y));
+                component.max = (component.max.0.max(x), component.max.1.max(y));
+                component.sum = (component.sum.0 + x as f64 + 0.5, component.sum.1 + y as f64 + 0.5);
+                component.touches_border |= x == 0 || y == 0 || x + 1 == width || y + 1 == height;
+                labels[i] = indices[root];
+            }
+        }
+        (labels, components)
+    }
+}
+
+/// Returns the root of the union-find tree holding `i`, halving the path on the way
+fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+/// Returns the grey level best separating dark from light pixels, by Otsu's method
+fn otsu_threshold(image: &GrayImage) -> u8 {
+    let mut histogram = [0u64; 256];
+    for pixel in image.pixels() {
+        histogram[pixel.0[0] as usize] += 1;
+    }
+    let total: u64 = histogram.iter().sum();
+    let total_sum: f64 = histogram.iter().enumerate().map(|(level, &count)| level as f64 * count as f64).sum();
+    let (mut below, mut below_sum) = (0u64, 0.0);
+    let (mut best, mut best_variance) = (0u8, -1.0);
+    for (level, &count) in histogram.iter().enumerate().take(255) {
+        below += count;
+        below_sum += level as f64 * count as f64;
+        let above = total - below;
+        if below == 0 || above == 0 {
+            continue;
+        }
+        let difference = below_sum / below as f64 - (total_sum - below_sum) / above as f64;
+        let variance = below as f64 * above as f64 * difference * difference;
+        if variance > best_variance {
+            best = level as u8;
+            best_variance = variance;
+        }
+    }
+    best
+}
+
+/// Returns the most plausible finder pattern triples, as top left, top right and bottom left
+///
+/// The top left pattern sits at the right angle, with the other two at equal distances and
+/// the top right one clockwise from the bottom left one.
+fn finder_triples(finders: &[Pattern]) -> Vec<(Pattern, Pattern, Pattern)> {
+    let mut triples = Vec::new();
+    for i in 0..finders.len() {
+        for j in i + 1..finders.len() {
+            for k in j + 1..finders.len() {
+                for (corner, a, b) in [(i, j, k), (j, k, i), (k, i, j)] {
+                    let (corner, mut a, mut b) = (finders[corner], finders[a], finders[b]);
+                    let legs = (
+                        (a.centre.0 - corner.centre.0, a.centre.1 - corner.centre.1),
+                        (b.centre.0 - corner.centre.0, b.centre.1 - corner.centre.1),
+                    );
+                    let lengths = (legs.0 .0.hypot(legs.0 .1), legs.1 .0.hypot(legs.1 .1));
+                    let modules = [corner.module, a.module, b.module];
+                    let largest = modules.iter().copied().fold(0.0, f64::max);
+                    let smallest = modules.iter().copied().fold(f64::MAX, f64::min);
+                    let cosine = (legs.0 .0 * legs.1 .0 + legs.0 .1 * legs.1 .1).abs() / (lengths.0 * lengths.1);
+                    let imbalance = (lengths.0 - lengths.1).abs() / lengths.0.max(lengths.1);
+                    let spread = (largest - smallest) / largest;
+                    let modules_apart = lengths.0.min(lengths.1) / largest;
+                    if cosine > 0.4 || imbalance > 0.4 || spread > 0.5 || !(10.0..200.0).contains(&modules_apart) {
+                        continue;
+                    }
+                    if legs.0 .0 * legs.1 .1 - legs.0 .1 * legs.1 .0 < 0.0 {
+                        std::mem::swap(&mut a, &mut b);
+                    }
+                    triples.push((cosine + imbalance + spread, (corner, a, b)));
+                }
+            }
+        }
+    }
+    triples.sort_by(|a, b| a.0.total_cmp(&b.0));
+    triples.into_iter().take(MAX_TRIPLES).map(|(_, triple)| triple).collect()
+}
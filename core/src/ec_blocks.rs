@@ -0,0 +1,160 @@
+use crate::encoding::ErrorCorrectionLevel;
+
+/// A Reed-Solomon block: a run of data codewords protected by its own error correction codewords
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RsBlock {
+    pub data_codewords: usize,
+    pub ec_codewords: usize,
+}
+
+/// `(ec codewords per block, blocks in group 1, data codewords per group 1 block,
+/// blocks in group 2, data codewords per group 2 block)`
+type BlockGroups = (usize, usize, usize, usize, usize);
+
+/// Reed-Solomon block structure per version (1-40) and error correction level (L, M, Q, H),
+/// as in ISO/IEC 18004 Table 9
+static RS_BLOCK_TABLE: [[BlockGroups; 4]; 40] = [
+    [(7, 1, 19, 0, 0), (10, 1, 16, 0, 0), (13, 1, 13, 0, 0), (17, 1, 9, 0, 0)], // 1
+    [(10, 1, 34, 0, 0), (16, 1, 28, 0, 0), (22, 1, 22, 0, 0), (28, 1, 16, 0, 0)], // 2
+    [(15, 1, 55, 0, 0), (26, 1, 44, 0, 0), (18, 2, 17, 0, 0), (22, 2, 13, 0, 0)], // 3
+    [(20, 1, 80, 0, 0), (18, 2, 32, 0, 0), (26, 2, 24, 0, 0), (16, 4, 9, 0, 0)], // 4
+    [(26, 1, 108, 0, 0), (24, 2, 43, 0, 0), (18, 2, 15, 2, 16), (22, 2, 11, 2, 12)], // 5
+    [(18, 2, 68, 0, 0), (16, 4, 27, 0, 0), (24, 4, 19, 0, 0), (28, 4, 15, 0, 0)], // 6
+    [(20, 2, 78, 0, 0), (18, 4, 31, 0, 0), (18, 2, 14, 4, 15), (26, 4, 13, 1, 14)], // 7
+    [(24, 2, 97, 0, 0), (22, 2, 38, 2, 39), (22, 4, 18, 2, 19), (26, 4, 14, 2, 15)], // 8
+    [(30, 2, 116, 0, 0), (22, 3, 36, 2, 37), (20, 4, 16, 4, 17), (24, 4, 12, 4, 13)], // 9
+    [(18, 2, 68, 2, 69), (26, 4, 43, 1, 44), (24, 6, 19, 2, 20), (28, 6, 15, 2, 16)], // 10
+    [(20, 4, 81, 0, 0), (30, 1, 50, 4, 51), (28, 4, 22, 4, 23), (24, 3, 12, 8, 13)], // 11
+    [(24, 2, 92, 2, 93), (22, 6, 36, 2, 37), (26, 4, 20, 6, 21), (28, 7, 14, 4, 15)], // 12
+    [(26, 4, 107, 0, 0), (22, 8, 37, 1, 38), (24, 8, 20, 4, 21), (22, 12, 11, 4, 12)], // 13
+    [(30, 3, 115, 1, 116), (24, 4, 40, 5, 41), (20, 11, 16, 5, 17), (24, 11, 12, 5, 13)], // 14
+    [(22, 5, 87, 1, 88), (24, 5, 41, 5, 42), (30, 5, 24, 7, 25), (24, 11, 12, 7, 13)], // 15
+    [(24, 5, 98, 1, 99), (28, 7, 45, 3, 46), (24, 15, 19, 2, 20), (30, 3, 15, 13, 16)], // 16
+    [(28, 1, 107, 5, 108), (28, 10, 46, 1, 47), (28, 1, 22, 15, 23), (28, 2, 14, 17, 15)], // 17
+    [(30, 5, 120, 1, 121), (26, 9, 43, 4, 44), (28, 17, 22, 1, 23), (28, 2, 14, 19, 15)], // 18
+    [(28, 3, 113, 4, 114), (26, 3, 44, 11, 45), (26, 17, 21, 4, 22), (26, 9, 13, 16, 14)], // 19
+    [(28, 3, 107, 5, 108), (26, 3, 41, 13, 42), (30, 15, 24, 5, 25), (28, 15, 15, 10, 16)], // 20
+    [(28, 4, 116, 4, 117), (26, 17, 42, 0, 0), (28, 17, 22, 6, 23), (30, 19, 16, 6, 17)], // 21
+    [(28, 2, 111, 7, 112), (28, 17, 46, 0, 0), (30, 7, 24, 16, 25), (24, 34, 13, 0, 0)], // 22
+    [(30, 4, 121, 5, 122), (28, 4, 47, 14, 48), (30, 11, 24, 14, 25), (30, 16, 15, 14, 16)], // 23
+    [(30, 6, 117, 4, 118), (28, 6, 45, 14, 46), (30, 11, 24, 16, 25), (30, 30, 16, 2, 17)], // 24
+    [(26, 8, 106, 4, 107), (28, 8, 47, 13, 48), (30, 7, 24, 22, 25), (30, 22, 15, 13, 16)], // 25
+    [(28, 10, 114, 2, 115), (28, 19, 46, 4, 47), (28, 28, 22, 6, 23), (30, 33, 16, 4, 17)], // 26
+    [(30, 8, 122, 4, 123), (28, 22, 45, 3, 46), (30, 8, 23, 26, 24), (30, 12, 15, 28, 16)], // 27
+    [(30, 3, 117, 10, 118), (28, 3, 45, 23, 46), (30, 4, 24, 31, 25), (30, 11, 15, 31, 16)], // 28
+    [(30, 7, 116, 7, 117), (28, 21, 45, 7, 46), (30, 1, 23, 37, 24), (30, 19, 15, 26, 16)], // 29
+    [(30, 5, 115, 10, 116), (28, 19, 47, 10, 48), (30, 15, 24, 25, 25), (30, 23, 15, 25, 16)], // 30
+    [(30, 13, 115, 3, 116), (28, 2, 46, 29, 47), (30, 42, 24, 1, 25), (30, 23, 15, 28, 16)], // 31
+    [(30, 17, 115, 0, 0), (28, 10, 46, 23, 47), (30, 10, 24, 35, 25), (30, 19, 15, 35, 16)], // 32
+    [(30, 17, 115, 1, 116), (28, 14, 46, 21, 47), (30, 29, 24, 19, 25), (30, 11, 15, 46, 16)], // 33
+    [(30, 13, 115, 6, 116), (28, 14, 46, 23, 47), (30, 44, 24, 7, 25), (30, 59, 16, 1, 17)], // 34
+    [(30, 12, 121, 7, 122), (28, 12, 47, 26, 48), (30, 39, 24, 14, 25), (30, 22, 15, 41, 16)], // 35
+    [(30, 6, 121, 14, 122), (28, 6, 47, 34, 48), (30, 46, 24, 10, 25), (30, 2, 15, 64, 16)], // 36
+    [(30, 17, 122, 4, 123), (28, 29, 46, 14, 47), (30, 49, 24, 10, 25), (30, 24, 15, 46, 16)], // 37
+    [(30, 4, 122, 18, 123), (28, 13, 46, 32, 47), (30, 48, 24, 14, 25), (30, 42, 15, 32, 16)], // 38
+    [(30, 20, 117, 4, 118), (28, 40, 47, 7, 48), (30, 43, 24, 22, 25), (30, 10, 15, 67, 16)], // 39
+    [(30, 19, 118, 6, 119), (28, 18, 47, 31, 48), (30, 34, 24, 34, 25), (30, 20, 15, 61, 16)], // 40
+];
+
+/// Returns the Reed-Solomon blocks of a symbol, in the order their codewords are interleaved
+pub(crate) fn rs_blocks(version: u8, level: ErrorCorrectionLevel) -> Vec<RsBlock> {
+    let (ec_codewords, group_1_blocks, group_1_data, group_2_blocks, group_2_data) =
+        RS_BLOCK_TABLE[version as usize - 1][level_index(level)];
+    let group_1 = std::iter::repeat_n(RsBlock { data_codewords: group_1_data, ec_codewords }, group_1_blocks);
+    let group_2 = std::iter::repeat_n(RsBlock { data_codewords: group_2_data, ec_codewords }, group_2_blocks);
+    group_1.chain(group_2).collect()
+}
+
+/// Number of erroneous codewords each block of the symbol can correct
+///
+/// Small symbols reserve a few error correction codewords for misdecode
+/// protection (ISO/IEC 18004 Table 9, `p`), which cannot be spent on corrections.
+pub(crate) fn correctable_errors_per_block(version: u8, level: ErrorCorrectionLevel) -> usize {
+    let misdecode_protection = match (version, level) {
+        (1, ErrorCorrectionLevel::Low) => 3,
+        (1, ErrorCorrectionLevel::Medium) | (2, ErrorCorrectionLevel::Low) => 2,
+        (1, _) | (3, ErrorCorrectionLevel::Low) => 1,
+        _ => 0,
+    };
+    let (ec_codewords, ..) = RS_BLOCK_TABLE[version as usize - 1][level_index(level)];
+    (ec_codewords - misdecode_protection) / 2
+}
+
+/// Maps every codeword, in placement order, to the index of the block it belongs to
+///
+/// Data codewords are interleaved across the blocks first, one from each block in
+/// turn, followed by the error correction codewords interleaved the same way.
+pub(crate) fn codeword_blocks(blocks: &[RsBlock]) -> Vec<usize> {
+    let longest_data = blocks.iter().map(|block| block.data_codewords).max().unwrap_or(0);
+    let longest_ec = blocks.iter().map(|block| block.ec_codewords).max().unwrap_or(0);
+    let data = (0..longest_data)
+        .flat_map(|i| (0..blocks.len()).filter(move |&block| i < blocks[block].data_codewords));
+    let ec = (0..longest_ec)
+        .flat_map(|i| (0..blocks.len()).filter(move |&block| i < blocks[block].ec_codewords));
+    data.chain(ec).collect()
+}
+
+fn level_index(level: ErrorCorrectionLevel) -> usize {
+    match level {
+        ErrorCorrectionLevel::Low => 0,
+        ErrorCorrectionLevel::Medium => 1,
+        ErrorCorrectionLevel::Quartile => 2,
+        ErrorCorrectionLevel::High => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qrcode::Version;
+
+    const LEVELS: [ErrorCorrectionLevel; 4] = [
+        ErrorCorrectionLevel::Low,
+        ErrorCorrectionLevel::Medium,
+        ErrorCorrectionLevel::Quartile,
+        ErrorCorrectionLevel::High,
+    ];
+
+    #[test]
+    fn matches_qrcode_crate_interleaving() {
+        for version in 1..=40u8 {
+            for level in LEVELS {
+                let blocks = rs_blocks(version, level);
+                // Tag every data codeword with its block so the interleaved output reveals the layout
+                let raw: Vec<u8> = blocks
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(index, block)| std::iter::repeat_n(index as u8, block.data_codewords))
+                    .collect();
+                let (data, ec) =
+                    qrcode::ec::construct_codewords(&raw, Version::Normal(version as i16), level.into()).unwrap();
+                let owners = codeword_blocks(&blocks);
+                assert_eq!(owners.len(), data.len() + ec.len());
+                assert!(owners.iter().zip(&data).all(|(&owner, &tag)| owner == tag as usize));
+            }
+        }
+    }
+
+    #[test]
+    fn matches_qrcode_crate_error_budget() {
+        for version in 1..=40u8 {
+            for level in LEVELS {
+                let blocks = rs_blocks(version, level);
+                let total = correctable_errors_per_block(version, level) * blocks.len();
+                let expected = qrcode::ec::max_allowed_errors(Version::Normal(version as i16), level.into()).unwrap();
+                // The crate rounds over the whole symbol, we round per block
+                assert!(total <= expected && expected - total < blocks.len(), "v{version} {level:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn splits_version_5_quartile_into_two_groups() {
+        let blocks = rs_blocks(5, ErrorCorrectionLevel::Quartile);
+        let sizes: Vec<usize> = blocks.iter().map(|block| block.data_codewords).collect();
+        assert_eq!(sizes, [15, 15, 16, 16]);
+        assert!(blocks.iter().all(|block| block.ec_codewords == 18));
+        assert_eq!(correctable_errors_per_block(5, ErrorCorrectionLevel::Quartile), 9);
+        assert_eq!(correctable_errors_per_block(1, ErrorCorrectionLevel::Low), 2);
+    }
+}
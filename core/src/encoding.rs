@@ -0,0 +1,281 @@
+use qrcode::bits::Bits;
+use qrcode::canvas::{Canvas, MaskPattern};
+use qrcode::{Color, EcLevel, Version};
+use crate::error::QrError;
+use crate::mask::{penalty_score, MASK_PATTERN_COUNT};
+
+/// Smallest QR code version (21x21 modules)
+pub const MIN_VERSION: u8 = 1;
+/// Largest QR code version (177x177 modules)
+pub const MAX_VERSION: u8 = 40;
+
+/// Error correction level used when encoding the QR code
+///
+/// Higher levels can recover from more damage (scuffs, logos, styling) at the
+/// cost of a denser symbol for the same data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorCorrectionLevel {
+    /// Recovers roughly 7% of codewords
+    Low,
+    /// Recovers roughly 15% of codewords
+    #[default]
+    Medium,
+    /// Recovers roughly 25% of codewords
+    Quartile,
+    /// Recovers roughly 30% of codewords
+    High,
+}
+
+impl From<ErrorCorrectionLevel> for EcLevel {
+    fn from(level: ErrorCorrectionLevel) -> Self {
+        match level {
+            ErrorCorrectionLevel::Low => EcLevel::L,
+            ErrorCorrectionLevel::Medium => EcLevel::M,
+            ErrorCorrectionLevel::Quartile => EcLevel::Q,
+            ErrorCorrectionLevel::High => EcLevel::H,
+        }
+    }
+}
+
+/// Constraint on the QR code version (symbol size) used for the encoded data
+///
+/// Version `n` produces a symbol of `17 + 4 * n` modules per side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VersionConstraint {
+    /// Use the smallest version that fits the data
+    #[default]
+    Auto,
+    /// Always use exactly this version
+    Fixed(u8),
+    /// Use the smallest version within the inclusive range that fits the data
+    Range { min: u8, max: u8 },
+}
+
+impl VersionConstraint {
+    /// Returns the inclusive (min, max) version bounds of this constraint
+    fn bounds(self) -> Result<(u8, u8), QrError> {
+        let (min, max) = match self {
+            VersionConstraint::Auto => (MIN_VERSION, MAX_VERSION),
+            VersionConstraint::Fixed(version) => (version, version),
+            VersionConstraint::Range { min, max } => (min, max),
+        };
+        for version in [min, max] {
+            if !(MIN_VERSION..=MAX_VERSION).contains(&version) {
+                return Err(QrError::InvalidConfig(format!(
+                    "version {version} is outside {MIN_VERSION}..={MAX_VERSION}"
+                )));
+            }
+        }
+        if min > max {
+            return Err(QrError::InvalidConfig(format!(
+                "minimum version {min} is greater than maximum version {max}"
+            )));
+        }
+        Ok((min, max))
+    }
+}
+
+/// Mask pattern applied to the data region of the symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MaskSelection {
+    /// Use the mask with the lowest ISO/IEC 18004 penalty score
+    #[default]
+    Auto,
+    /// Always use this mask pattern (0-7)
+    Fixed(u8),
+}
+
+/// Options controlling how data is encoded into the QR code matrix
+#[derive(Debug, Clone, Default)]
+pub struct QrEncodeOptions {
+    /// Error correction level of the generated symbol
+    pub error_correction: ErrorCorrectionLevel,
+    /// Version (symbol size) the data is encoded into
+    pub version: VersionConstraint,
+    /// Mask pattern applied to the data modules
+    pub mask: MaskSelection,
+}
+
+/// A fully encoded and masked QR code symbol
+pub(crate) struct EncodedQr {
+    pub version: u8,
+    pub error_correction: ErrorCorrectionLevel,
+    pub mask: u8,
+    /// Penalty score of every mask pattern, indexed by mask number
+    pub mask_penalties: [u32; MASK_PATTERN_COUNT],
+    pub width: usize,
+    /// Modules in row-major order, `true` meaning dark
+    pub modules: Vec<bool>,
+    /// Number of data codewords, which precede the error correction codewords in placement order
+    pub data_codewords: usize,
+}
+
+/// Encodes the data with the qrcode crate, honouring the version and mask options
+pub(crate) fn encode_qr_code(data: &str, options: &QrEncodeOptions) -> Result<EncodedQr, QrError> {
+    let (min, max) = options.version.bounds()?;
+    let forced_mask = match options.mask {
+        MaskSelection::Auto => None,
+        MaskSelection::Fixed(mask) if (mask as usize) < MASK_PATTERN_COUNT => Some(mask),
+        MaskSelection::Fixed(mask) => {
+            return Err(QrError::InvalidConfig(format!("mask pattern {mask} is outside 0..=7")));
+        }
+    };
+    let ec_level = options.error_correction.into();
+
+    // Try each allowed version from smallest to largest until the data fits
+    let (version, bits) = (min..=max)
+        .find_map(|version| {
+            let mut bits = Bits::new(Version::Normal(version as i16));
+            match bits.push_optimal_data(data.as_bytes()).and_then(|_| bits.push_terminator(ec_level)) {
+                Ok(()) => Some(Ok((version, bits))),
+                Err(qrcode::types::QrError::DataTooLong) => None,
+                Err(e) => Some(Err(QrError::Encode(e.to_string()))),
+            }
+        })
+        .unwrap_or(Err(QrError::DataTooLong { max_version: max }))?;
+
+    let (data_codewords, ec_codewords) =
+        qrcode::ec::construct_codewords(&bits.into_bytes(), Version::Normal(version as i16), ec_level)
+            .map_err(|e| QrError::Encode(e.to_string()))?;
+    let mut canvas = Canvas::new(Version::Normal(version as i16), ec_level);
+    canvas.draw_all_functional_patterns();
+    canvas.draw_data(&data_codewords, &ec_codewords);
+
+    // Score every mask so callers can compare them, even when one is forced
+    let width = Version::Normal(version as i16).width() as usize;
+    let masked: Vec<Vec<bool>> = (0..MASK_PATTERN_COUNT as u8)
+        .map(|mask| {
+            let mut candidate = canvas.clone();
+            candidate.apply_mask(mask_pattern(mask));
+            candidate.into_colors().into_iter().map(|color| color == Color::Dark).collect()
+        })
+        .collect();
+    let mut mask_penalties = [0; MASK_PATTERN_COUNT];
+    for (penalty, modules) in mask_penalties.iter_mut().zip(&masked) {
+        *penalty = penalty_score(modules, width);
+    }
+    let mask = forced_mask.unwrap_or_else(|| {
+        // Lowest penalty wins, ties go to the lowest mask number
+        (0..MASK_PATTERN_COUNT).min_by_key(|&mask| mask_penalties[mask]).unwrap_or(0) as u8
+    });
+
+    Ok(EncodedQr {
+        version,
+        error_correction: options.error_correction,
+        mask,
+        mask_penalties,
+        width,
+        modules: masked.into_iter().nth(mask as usize).unwrap_or_default(),
+        data_codewords: data_codewords.len(),
+    })
+}
+
+/// Maps an ISO mask number (0-7) to the qrcode crate's pattern
+fn mask_pattern(mask: u8) -> MaskPattern {
+    match mask {
+        0 => MaskPattern::Checkerboard,
+        1 => MaskPattern::HorizontalLines,
+        2 => MaskPattern::VerticalLines,
+        3 => MaskPattern::DiagonalLines,
+        4 => MaskPattern::LargeCheckerboard,
+        5 => MaskPattern::Fields,
+        6 => MaskPattern::Diamonds,
+        _ => MaskPattern::Meadow,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_error_correction_levels() {
+        assert_eq!(EcLevel::from(ErrorCorrectionLevel::Low), EcLevel::L);
+        assert_eq!(EcLevel::from(ErrorCorrectionLevel::Medium), EcLevel::M);
+        assert_eq!(EcLevel::from(ErrorCorrectionLevel::Quartile), EcLevel::Q);
+        assert_eq!(EcLevel::from(ErrorCorrectionLevel::High), EcLevel::H);
+        assert_eq!(ErrorCorrectionLevel::default(), ErrorCorrectionLevel::Medium);
+    }
+
+    fn options(version: VersionConstraint) -> QrEncodeOptions {
+        QrEncodeOptions { version, ..QrEncodeOptions::default() }
+    }
+
+    #[test]
+    fn picks_smallest_version_by_default() {
+        let code = encode_qr_code("https://jedi.org", &QrEncodeOptions::default()).unwrap();
+        let auto = qrcode::QrCode::with_error_correction_level("https://jedi.org", EcLevel::M).unwrap();
+        assert_eq!(Version::Normal(code.version as i16), auto.version());
+        assert_eq!(code.version, 2);
+    }
+
+    #[test]
+    fn honours_fixed_and_ranged_versions() {
+        let code = encode_qr_code("https://jedi.org", &options(VersionConstraint::Fixed(7))).unwrap();
+        assert_eq!(code.version, 7);
+        assert_eq!(code.width, 45);
+
+        let range = VersionConstraint::Range { min: 3, max: 10 };
+        let code = encode_qr_code("https://jedi.org", &options(range)).unwrap();
+        assert_eq!(code.version, 3);
+    }
+
+    #[test]
+    fn reports_data_that_does_not_fit() {
+        let data = "x".repeat(100);
+        let result = encode_qr_code(&data, &options(VersionConstraint::Fixed(2)));
+        assert!(matches!(result, Err(QrError::DataTooLong { max_version: 2 })));
+
+        let data = "x".repeat(5000);
+        let result = encode_qr_code(&data, &QrEncodeOptions::default());
+        assert!(matches!(result, Err(QrError::DataTooLong { max_version: 40 })));
+    }
+
+    #[test]
+    fn rejects_invalid_version_constraints() {
+        for version in [
+            VersionConstraint::Fixed(0),
+            VersionConstraint::Fixed(41),
+            VersionConstraint::Range { min: 10, max: 5 },
+        ] {
+            let result = encode_qr_code("https://jedi.org", &options(version));
+            assert!(matches!(result, Err(QrError::InvalidConfig(_))));
+        }
+    }
+
+    #[test]
+    fn selects_lowest_penalty_mask_automatically() {
+        let code = encode_qr_code("https://jedi.org", &QrEncodeOptions::default()).unwrap();
+        let lowest = *code.mask_penalties.iter().min().unwrap();
+        assert_eq!(code.mask_penalties[code.mask as usize], lowest);
+        assert!(code.mask_penalties.iter().all(|&penalty| penalty > 0));
+    }
+
+    #[test]
+    fn matches_qrcode_crate_output_for_same_mask() {
+        // One of the candidate masks must reproduce the symbol the qrcode crate builds itself
+        let reference = qrcode::QrCode::with_error_correction_level("https://jedi.org", EcLevel::M).unwrap();
+        let reference: Vec<bool> = reference.into_colors().into_iter().map(|c| c == Color::Dark).collect();
+        let matches = (0..8).any(|mask| {
+            let options = QrEncodeOptions { mask: MaskSelection::Fixed(mask), ..QrEncodeOptions::default() };
+            encode_qr_code("https://jedi.org", &options).unwrap().modules == reference
+        });
+        assert!(matches);
+    }
+
+    #[test]
+    fn honours_forced_mask() {
+        let auto = encode_qr_code("https://jedi.org", &QrEncodeOptions::default()).unwrap();
+        for mask in 0..8 {
+            let options = QrEncodeOptions { mask: MaskSelection::Fixed(mask), ..QrEncodeOptions::default() };
+            let code = encode_qr_code("https://jedi.org", &options).unwrap();
+            assert_eq!(code.mask, mask);
+            // Penalties describe the candidates, not the choice, so they never change
+            assert_eq!(code.mask_penalties, auto.mask_penalties);
+            assert_eq!(code.modules == auto.modules, mask == auto.mask);
+        }
+
+        let options = QrEncodeOptions { mask: MaskSelection::Fixed(8), ..QrEncodeOptions::default() };
+        assert!(matches!(encode_qr_code("https://jedi.org", &options), Err(QrError::InvalidConfig(_))));
+    }
+}
@@ -0,0 +1,65 @@
+use thiserror::Error;
+
+/// Errors raised anywhere in the QR code pipeline, from encoding to rasterizing
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum QrError {
+    /// The data does not fit in the largest allowed version at the chosen error correction level
+    #[error("Data does not fit in a QR code of version {max_version} or lower at the requested error correction level")]
+    DataTooLong { max_version: u8 },
+
+    /// The configuration contains an invalid or contradictory value
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    /// The generated SVG could not be parsed for rasterization
+    #[error("Failed to parse SVG: {0}")]
+    SvgParse(String),
+
+    /// The SVG could not be rendered to a pixel buffer
+    #[error("Failed to render SVG: {0}")]
+    Render(String),
+
+    /// The logo hides more codewords of a block than its error correction can recover
+    #[error("Logo hides {damaged_codewords} codewords of a block that can only correct {correctable_codewords}; use a smaller logo or a higher error correction level")]
+    LogoTooLarge { damaged_codewords: usize, correctable_codewords: usize },
+
+    /// The data or image could not be encoded
+    #[error("Failed to encode: {0}")]
+    Encode(String),
+
+    /// The rendered code did not decode back to the input data
+    #[error("Verification failed: {0}")]
+    Verification(String),
+}
+
+impl QrError {
+    /// Machine-readable identifier for the error variant, stable across releases
+    pub fn code(&self) -> &'static str {
+        match self {
+            QrError::DataTooLong { .. } => "DATA_TOO_LONG",
+            QrError::InvalidConfig(_) => "INVALID_CONFIG",
+            QrError::SvgParse(_) => "SVG_PARSE",
+            QrError::Render(_) => "RENDER",
+            QrError::Encode(_) => "ENCODE",
+            QrError::LogoTooLarge { .. } => "LOGO_TOO_LARGE",
+            QrError::Verification(_) => "VERIFICATION_FAILED",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exposes_stable_error_codes() {
+        assert_eq!(QrError::DataTooLong { max_version: 40 }.code(), "DATA_TOO_LONG");
+        assert_eq!(QrError::InvalidConfig("bad".to_string()).code(), "INVALID_CONFIG");
+        assert_eq!(QrError::SvgParse("bad".to_string()).code(), "SVG_PARSE");
+        assert_eq!(QrError::Render("bad".to_string()).code(), "RENDER");
+        assert_eq!(QrError::Encode("bad".to_string()).code(), "ENCODE");
+        let logo = QrError::LogoTooLarge { damaged_codewords: 20, correctable_codewords: 13 };
+        assert_eq!(logo.code(), "LOGO_TOO_LARGE");
+        assert_eq!(QrError::Verification("bad".to_string()).code(), "VERIFICATION_FAILED");
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt::Write;
+use crate::ec_blocks::{codeword_blocks, correctable_errors_per_block, rs_blocks};
+use crate::error::QrError;
+use crate::format_conversion::QrCodeResult;
+use crate::module_shape::round;
+use crate::qr_matrix::{data_module_positions, QrMatrix};
+use crate::svg_rendering::DEFAULT_PRECISION;
+use crate::verification::sample_grid;
+
+/// Codeword errors read back from one Reed-Solomon block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockErrors {
+    pub data_codewords: usize,
+    pub ec_codewords: usize,
+    /// Codewords with at least one misread module
+    pub errors: usize,
+    /// Most erroneous codewords the block can correct at the symbol's error correction level
+    pub correctable: usize,
+}
+
+impl BlockErrors {
+    /// Whether a decoder can still correct every error of the block
+    pub fn within_budget(&self) -> bool {
+        self.errors <= self.correctable
+    }
+}
+
+/// How much of its Reed-Solomon error budget a rendered code spends before it is even damaged
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorBudgetReport {
+    /// Every block, in the order their codewords are interleaved
+    pub blocks: Vec<BlockErrors>,
+    /// Coordinates of every module the decoder read with the wrong color, function patterns included
+    pub misread_modules: Vec<(usize, usize)>,
+    /// Width of the symbol in modules
+    pub symbol_width: usize,
+    /// Width of the quiet zone around the symbol in modules, fractional when snapped to pixels
+    pub quiet_zone: f32,
+    /// Size of the rendered output, which the heatmap matches so it can be laid over it
+    pub width: u32,
+    pub height: u32,
+    misread_blocks: Vec<Option<usize>>,
+}
+
+impl ErrorBudgetReport {
+    /// Whether every block is within its budget, so the code decodes despite the misreads
+    pub fn is_recoverable(&self) -> bool {
+        self.blocks.iter().all(BlockErrors::within_budget)
+    }
+
+    /// Returns an SVG the size of the rendered code highlighting every misread module
+    ///
+    /// Misread modules are painted red, more opaque the more of its block's budget their
+    /// codeword's block has spent, and fully opaque in blocks over budget and in function
+    /// patterns, which no error correction protects. Everything else is transparent.
+    pub fn heatmap_svg(&self) -> String {
+        let extent = self.symbol_width as f32 + 2.0 * self.quiet_zone;
+        let mut svg = format!(
+            "<svg xmlns='http://www.w3.org/2000/svg' width='{}' height='{}' viewBox='{} {} {extent} {extent}'>",
+            self.width, self.height, -self.quiet_zone, -self.quiet_zone
+        );
+        for (&(x, y), block) in self.misread_modules.iter().zip(&self.misread_blocks) {
+            let opacity = match block.map(|block| self.blocks[block]) {
+                Some(block) if block.within_budget() => {
+                    0.3 + 0.5 * block.errors as f32 / block.correctable.max(1) as f32
+                }
+                _ => 1.0,
+            };
+            let _ = write!(svg, "<rect x='{x}' y='{y}' width='1' height='1' fill='#ff0000' fill-opacity='{opacity:.2}'/>");
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+}
+
+impl QrCodeResult {
+    /// Compares the modules a decoder samples from the rendered code with `matrix`, the ideal
+    /// matrix it was rendered from, and counts the codeword errors of every Reed-Solomon block
+    ///
+    /// The output is rasterized like `verify` does. Fails with `QrError::Verification` when no
+    /// code is found or it does not match the matrix in size.
+    pub fn error_budget(&self, matrix: &QrMatrix) -> Result<ErrorBudgetReport, QrError> {
+        let (image, _) = self.greyscale()?;
+        let sampled = sample_grid(&image)?;
+        let (sampled_width, symbol_width) = (sampled.width, matrix.width());
+        if sampled_width != symbol_width {
+            return Err(QrError::Verification(format!(
+                "found a {sampled_width} module wide code instead of the {symbol_width} module wide symbol"
+            )));
+        }
+
+        let blocks = rs_blocks(matrix.version(), matrix.error_correction());
+        let owners = codeword_blocks(&blocks);
+        // Block of the codeword each module carries a bit of, if any
+        let mut module_blocks = vec![None; symbol_width * symbol_width];
+        let mut erroneous = vec![false; owners.len()];
+        for (index, (x, y)) in data_module_positions(matrix.version()).into_iter().enumerate() {
+            let codeword = index / 8;
+            // Remainder bits after the last codeword carry no data
+            if codeword < owners.len() {
+                module_blocks[y * symbol_width + x] = Some(owners[codeword]);
+                erroneous[codeword] |= sampled.is_dark(x, y) != matrix.is_dark(x, y);
+            }
+        }
+        let mut errors = vec![0; blocks.len()];
+        for (codeword, _) in erroneous.iter().enumerate().filter(|&(_, &erroneous)| erroneous) {
+            errors[owners[codeword]] += 1;
+        }
+        let correctable = correctable_errors_per_block(matrix.version(), matrix.error_correction());
+
+        let (misread_modules, misread_blocks) = (0..symbol_width * symbol_width)
+            .filter(|&i| sampled.modules[i] != matrix.is_dark(i % symbol_width, i / symbol_width))
+            .map(|i| ((i % symbol_width, i / symbol_width), module_blocks[i]))
+            .unzip();
+        let modules = self.width as f32 / self.module_size;
+        Ok(ErrorBudgetReport {
+            blocks: blocks
+                .iter()
+                .zip(errors)
+                .map(|(block, errors)| BlockErrors {
+                    data_codewords: block.data_codewords,
+                    ec_codewords: block.ec_codewords,
+                    errors,
+                    correctable,
+                })
+                .collect(),
+            misread_modules,
+            symbol_width,
+            quiet_zone: round((modules - symbol_width as f32) / 2.0, DEFAULT_PRECISION),
+            width: self.width,
+            height: self.height,
+            misread_blocks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        generate_qr_matrix, render_qr_code, DataShape, DataStyle, ErrorCorrectionLevel, FinderShape, Logo, LogoImage,
+        ModuleRole, QrEncodeOptions, QrRenderConfig, RasterFormat, VersionConstraint,
+    };
+
+    #[test]
+    fn reads_a_plain_code_without_errors() {
+        let data = "https://jedi.org/budget";
+        let result = render_qr_code(data, None, RasterFormat::Png, 400).unwrap();
+        let matrix = generate_qr_matrix(data, &QrEncodeOptions::default()).unwrap();
+        let report = result.error_budget(&matrix).unwrap();
+        assert!(report.misread_modules.is_empty());
+        assert!(report.blocks.iter().all(|block| block.errors == 0));
+        assert!(report.is_recoverable());
+        assert_eq!(report.quiet_zone, 4.0);
+    }
+
+    #[test]
+    fn reads_codes_with_styled_finder_patterns() {
+        let data = "https://jedi.org/budget";
+        let matrix = generate_qr_matrix(data, &QrEncodeOptions::default()).unwrap();
+        for (frame, eye) in [(FinderShape::Dot, FinderShape::Dot), (FinderShape::Triangle, FinderShape::Triangle)] {
+            let config = QrRenderConfig {
+                finder_frame_shape: frame,
+                finder_eye_shape: eye,
+                data_shape: DataShape::Square,
+                data_styling: DataStyle::color("black").unwrap(),
+                ..QrRenderConfig::default()
+            };
+            let report = render_qr_code(data, Some(&config), RasterFormat::Png, 400).unwrap().error_budget(&matrix).unwrap();
+            assert!(report.blocks.iter().all(|block| block.errors == 0), "{:?}", report.blocks);
+            // The corners the round and cut off shapes leave light are the only misreads
+            assert!(!report.misread_modules.is_empty());
+            assert!(report.misread_modules.iter().all(|&(x, y)| matrix.role(x, y) == ModuleRole::Finder));
+        }
+    }
+
+    #[test]
+    fn counts_codewords_hidden_by_a_logo() {
+        let data = "https://jedi.org/budget";
+        let config = QrRenderConfig {
+            encoding: QrEncodeOptions {
+                error_correction: ErrorCorrectionLevel::High,
+                version: VersionConstraint::Fixed(5),
+                ..QrEncodeOptions::default()
+            },
+            data_shape: DataShape::Square,
+            data_styling: DataStyle::color("black").unwrap(),
+            logo: Some(Logo::new(LogoImage::Svg("<svg xmlns='http://www.w3.org/2000/svg'/>".to_string()), 0.2)),
+            ..QrRenderConfig::default()
+        };
+        let result = render_qr_code(data, Some(&config), RasterFormat::Png, 400).unwrap();
+        let matrix = generate_qr_matrix(data, &config.encoding).unwrap();
+        let report = result.error_budget(&matrix).unwrap();
+
+        // Version 5-H has four blocks, each able to correct 11 codewords
+        assert_eq!(report.blocks.len(), 4);
+        assert!(report.blocks.iter().all(|block| block.correctable == 11));
+        assert!(report.blocks.iter().any(|block| block.errors > 0), "{:?}", report.blocks);
+        assert!(report.is_recoverable());
+        // Only dark modules under the blank logo are misread, all in the middle of the symbol
+        assert!(report.misread_modules.iter().all(|&(x, y)| (13..24).contains(&x) && (13..24).contains(&y)));
+
+        let heatmap = report.heatmap_svg();
+        assert!(heatmap.starts_with("<svg xmlns='http://www.w3.org/2000/svg' width='400' height='400' viewBox='-4 -4 45 45'>"));
+        assert_eq!(heatmap.matches("<rect").count(), report.misread_modules.len());
+    }
+
+    #[test]
+    fn refuses_mismatched_matrices() {
+        let result = render_qr_code("https://jedi.org", None, RasterFormat::Png, 300).unwrap();
+        let options = QrEncodeOptions { version: VersionConstraint::Fixed(10), ..QrEncodeOptions::default() };
+        let matrix = generate_qr_matrix("https://jedi.org", &options).unwrap();
+        assert!(matches!(result.error_budget(&matrix), Err(QrError::Verification(_))));
+    }
+}
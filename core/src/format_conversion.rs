@@ -1,25 +1,14 @@
 use std::io::Cursor;
-use thiserror::Error;
-use image::{ImageBuffer, Rgba, imageops};
-use resvg;
+use crate::encoding::ErrorCorrectionLevel;
+use crate::error::QrError;
+use crate::mask::MASK_PATTERN_COUNT;
+use image::{ImageBuffer, Rgb, RgbImage, Rgba, imageops};
+use crate::color::Color;
 use usvg::{Tree, Options, TreeParsing};
 
 const SUPER_SAMPLING_FACTOR: u32 = 2;
 const JPEG_QUALITY: u8 = 95;
 
-/// Custom error type for format conversion operations
-#[derive(Error, Debug)]
-pub enum FormatConversionError {
-    #[error("Failed to parse SVG: {0}")]
-    SvgParseError(String),
-    
-    #[error("Failed to render SVG: {0}")]
-    SvgRenderError(String),
-    
-    #[error("Failed to encode image: {0}")]
-    ImageEncodeError(String),
-}
-
 /// Supported raster output formats
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum RasterFormat {
@@ -41,10 +30,28 @@ pub enum QrCodeOutput {
 pub struct QrCodeResult {
     /// The rendered QR code data
     pub data: QrCodeOutput,
-    /// Width of the rendered QR code in pixels
+    /// Width of the rendered QR code in pixels, quiet zone included
+    /// For SVG output this is the width of the viewBox, whatever size the SVG declares
     pub width: u32,
-    /// Height of the rendered QR code in pixels
+    /// Height of the rendered QR code in pixels, quiet zone included
     pub height: u32,
+    /// Width and height of each module in pixels
+    pub module_size: f32,
+    /// Printed width in millimetres, when the SVG declares a physical size
+    pub physical_width_mm: Option<f32>,
+    /// Printed height in millimetres, when the SVG declares a physical size
+    pub physical_height_mm: Option<f32>,
+    /// Whether the code is inverted, with light modules on a dark ground, so readers that only
+    /// look for dark modules need the image inverted before decoding
+    pub inverted: bool,
+    /// Error correction level the QR code was encoded with
+    pub error_correction: ErrorCorrectionLevel,
+    /// QR code version (1-40) the data was encoded into
+    pub version: u8,
+    /// Mask pattern (0-7) applied to the symbol
+    pub mask: u8,
+    /// ISO/IEC 18004 penalty score of every mask pattern, indexed by mask number (lower is better)
+    pub mask_penalties: [u32; MASK_PATTERN_COUNT],
 }
 
 /// Converts an SVG string to a PNG image buffer with super-sampling for antialiasing
@@ -54,24 +61,25 @@ pub struct QrCodeResult {
 /// * `size` - The desired final output size in pixels
 /// 
 /// # Returns
-/// * `Result<ImageBuffer<Rgba<u8>, Vec<u8>>>` - The rendered and down-sampled image buffer
-fn svg_to_image_buffer(svg_string: &str, size: u32) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, FormatConversionError> {
+/// * `Result<ImageBuffer<Rgba<u8>, Vec<u8>>>` - The rendered and down-sampled image buffer,
+///   with premultiplied alpha so transparent pixels do not darken edges as they are down-sampled
+fn svg_to_image_buffer(svg_string: &str, size: u32) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, QrError> {
     // Parse the SVG string into a usvg Tree
     let tree = Tree::from_str(svg_string, &Options::default())
-        .map_err(|e| FormatConversionError::SvgParseError(e.to_string()))?;
+        .map_err(|e| QrError::SvgParse(e.to_string()))?;
     
     // Determine the size of the super-sampled pixmap
     let super_sampled_size = size * SUPER_SAMPLING_FACTOR;
     
     // Create a new image buffer with the super-sampled size
     let mut pixmap = resvg::tiny_skia::Pixmap::new(super_sampled_size, super_sampled_size)
-        .ok_or_else(|| FormatConversionError::SvgRenderError("Failed to create pixmap".to_string()))?;
+        .ok_or_else(|| QrError::Render("Failed to create pixmap".to_string()))?;
     
-    // Convert to resvg Tree and render at the super-sampled size
+    // Convert to resvg Tree and scale it so the whole SVG, quiet zone included, fills the super-sampled pixmap
     let rtree = resvg::Tree::from_usvg(&tree);
     let transform = resvg::tiny_skia::Transform::from_scale(
-        SUPER_SAMPLING_FACTOR as f32,
-        SUPER_SAMPLING_FACTOR as f32,
+        super_sampled_size as f32 / rtree.size.width(),
+        super_sampled_size as f32 / rtree.size.height(),
     );
     rtree.render(
         transform,
@@ -80,7 +88,7 @@ fn svg_to_image_buffer(svg_string: &str, size: u32) -> Result<ImageBuffer<Rgba<u
     
     // Convert the pixmap to an image buffer
     let super_sampled_buffer = ImageBuffer::from_raw(super_sampled_size, super_sampled_size, pixmap.data().to_vec())
-        .ok_or_else(|| FormatConversionError::SvgRenderError("Failed to create image buffer".to_string()))?;
+        .ok_or_else(|| QrError::Render("Failed to create image buffer".to_string()))?;
         
     // Down-sample the image to the target size with a high-quality filter
     let final_buffer = imageops::resize(
@@ -93,22 +101,55 @@ fn svg_to_image_buffer(svg_string: &str, size: u32) -> Result<ImageBuffer<Rgba<u
     Ok(final_buffer)
 }
 
+/// Converts premultiplied pixels to the straight alpha that PNG stores
+fn demultiply(image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>) {
+    for pixel in image.pixels_mut() {
+        let alpha = u32::from(pixel[3]);
+        for channel in &mut pixel.0[..3] {
+            // The down-sampling filter can overshoot a channel past its alpha
+            *channel = match alpha {
+                0 => 0,
+                _ => (u32::from(*channel) * 255 / alpha).min(255) as u8,
+            };
+        }
+    }
+}
+
+/// Composites premultiplied pixels over an opaque matte color
+fn flatten(image: &ImageBuffer<Rgba<u8>, Vec<u8>>, matte: Color) -> RgbImage {
+    let matte = [matte.red, matte.green, matte.blue];
+    RgbImage::from_fn(image.width(), image.height(), |x, y| {
+        let pixel = image.get_pixel(x, y);
+        let transparency = 255 - u32::from(pixel[3]);
+        Rgb(std::array::from_fn(|channel| {
+            let behind = (u32::from(matte[channel]) * transparency + 127) / 255;
+            (u32::from(pixel[channel]) + behind).min(255) as u8
+        }))
+    })
+}
+
 /// Converts an SVG string to the specified output format
 /// 
 /// # Arguments
 /// * `svg_string` - The SVG content as a string
 /// * `format` - The desired output format
 /// * `size` - The desired output size in pixels
+/// * `matte` - Opaque color that JPEG output, which has no alpha channel, is flattened onto
 /// 
 /// # Returns
-/// * `Result<QrCodeResult>` - The rendered QR code result
+/// * `Result<QrCodeOutput>` - The encoded raster output
 pub fn convert_svg_to_format(
     svg_string: &str,
     format: RasterFormat,
     size: u32,
-) -> Result<QrCodeResult, FormatConversionError> {
+    matte: Color,
+) -> Result<QrCodeOutput, QrError> {
+    if !matte.is_opaque() {
+        return Err(QrError::InvalidConfig(format!("matte color {matte} must be opaque")));
+    }
+
     // Convert SVG to a down-sampled image buffer for antialiasing
-    let image_buffer = svg_to_image_buffer(svg_string, size)?;
+    let mut image_buffer = svg_to_image_buffer(svg_string, size)?;
     
     // Create a buffer to hold the encoded image data
     let mut output_buffer = Vec::new();
@@ -117,26 +158,24 @@ pub fn convert_svg_to_format(
     // Encode the image buffer to the requested format
     match format {
         RasterFormat::Png => {
+            demultiply(&mut image_buffer);
             image_buffer
                 .write_to(&mut cursor, image::ImageFormat::Png)
-                .map_err(|e| FormatConversionError::ImageEncodeError(e.to_string()))?;
+                .map_err(|e| QrError::Encode(e.to_string()))?;
         }
         RasterFormat::Jpeg => {
+            let flattened = flatten(&image_buffer, matte);
             let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut cursor, JPEG_QUALITY);
             encoder.encode(
-                &image_buffer,
-                image_buffer.width(),
-                image_buffer.height(),
-                image::ColorType::Rgba8,
-            ).map_err(|e| FormatConversionError::ImageEncodeError(e.to_string()))?;
+                &flattened,
+                flattened.width(),
+                flattened.height(),
+                image::ColorType::Rgb8,
+            ).map_err(|e| QrError::Encode(e.to_string()))?;
         }
     }
     
-    Ok(QrCodeResult {
-        data: QrCodeOutput::Raster(output_buffer, format),
-        width: size,
-        height: size,
-    })
+    Ok(QrCodeOutput::Raster(output_buffer, format))
 }
 
 #[cfg(test)]
@@ -152,18 +191,75 @@ mod tests {
         </svg>"#;
         
         // Convert to PNG
-        let result = convert_svg_to_format(svg, RasterFormat::Png, 100);
+        let result = convert_svg_to_format(svg, RasterFormat::Png, 100, Color::WHITE);
         assert!(result.is_ok());
         
-        let qr_result = result.unwrap();
-        match qr_result.data {
+        match result.unwrap() {
             QrCodeOutput::Raster(data, format) => {
                 assert_eq!(format, RasterFormat::Png);
                 assert!(data.starts_with(b"\x89PNG\r\n\x1a\n"));
+                let decoded = image::load_from_memory(&data).unwrap();
+                assert_eq!(decoded.width(), 100);
+                assert_eq!(decoded.height(), 100);
             }
             _ => panic!("Expected Raster output"),
         }
-        assert_eq!(qr_result.width, 100);
-        assert_eq!(qr_result.height, 100);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn reports_invalid_svg_as_parse_error() {
+        let result = convert_svg_to_format("<not-svg", RasterFormat::Png, 100, Color::WHITE);
+        assert!(matches!(result, Err(QrError::SvgParse(_))));
+    }
+
+    #[test]
+    fn scales_svg_to_fill_requested_size() {
+        // A 200px SVG with a white margin and a black centre square
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="200">
+            <rect width="200" height="200" fill="white"/>
+            <rect x="40" y="40" width="120" height="120" fill="black"/>
+        </svg>"#;
+
+        let image = svg_to_image_buffer(svg, 100).unwrap();
+        // The margin scales down with the rest of the image rather than being cropped
+        assert_eq!(image.get_pixel(5, 5).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(50, 50).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(95, 95).0, [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn keeps_transparency_in_png() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+            <rect x="50" width="50" height="100" fill="rgb(200, 0, 0)" fill-opacity="0.5"/>
+        </svg>"#;
+        let QrCodeOutput::Raster(data, _) = convert_svg_to_format(svg, RasterFormat::Png, 100, Color::WHITE).unwrap() else {
+            panic!("Expected Raster output");
+        };
+        let image = image::load_from_memory(&data).unwrap().to_rgba8();
+        assert_eq!(image.get_pixel(10, 50).0[3], 0);
+        let [red, green, blue, alpha] = image.get_pixel(90, 50).0;
+        assert!(red.abs_diff(200) <= 1 && green == 0 && blue == 0 && alpha.abs_diff(128) <= 1, "{red} {green} {blue} {alpha}");
+    }
+
+    #[test]
+    fn flattens_jpeg_onto_matte() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+            <rect x="50" width="50" height="100" fill="black" fill-opacity="0.5"/>
+        </svg>"#;
+        let QrCodeOutput::Raster(data, _) = convert_svg_to_format(svg, RasterFormat::Jpeg, 100, Color::rgb(0, 0, 255)).unwrap() else {
+            panic!("Expected Raster output");
+        };
+        let image = image::load_from_memory(&data).unwrap().to_rgb8();
+        let close = |actual: [u8; 3], expected: [u8; 3]| actual.iter().zip(expected).all(|(a, e)| a.abs_diff(e) <= 8);
+        assert!(close(image.get_pixel(10, 50).0, [0, 0, 255]), "{:?}", image.get_pixel(10, 50));
+        assert!(close(image.get_pixel(90, 50).0, [0, 0, 127]), "{:?}", image.get_pixel(90, 50));
+    }
+
+    #[test]
+    fn refuses_translucent_mattes() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"/>"#;
+        let matte = Color { alpha: 128, ..Color::BLACK };
+        let result = convert_svg_to_format(svg, RasterFormat::Jpeg, 10, matte);
+        assert!(matches!(result, Err(QrError::InvalidConfig(_))));
+    }
+}
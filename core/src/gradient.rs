@@ -0,0 +1,169 @@
+use crate::color::{escape_xml, Color};
+use crate::error::QrError;
+use crate::module_shape::round;
+
+/// A colour at a given position along a gradient
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradientStop {
+    /// Position along the gradient, from 0.0 (start) to 1.0 (end)
+    pub offset: f32,
+    pub color: Color,
+}
+
+impl GradientStop {
+    pub fn new(offset: f32, color: Color) -> Self {
+        GradientStop { offset, color }
+    }
+}
+
+/// Gradient fill spanning the whole symbol rather than each module
+#[derive(Debug, Clone, PartialEq)]
+pub enum Gradient {
+    /// Runs across the symbol at `angle` degrees clockwise, 0 being left to right
+    Linear { angle: f32, stops: Vec<GradientStop> },
+    /// Radiates from `centre` out to `radius`, both as fractions of the symbol size
+    Radial { centre: (f32, f32), radius: f32, stops: Vec<GradientStop> },
+}
+
+impl Gradient {
+    /// Fails for non-finite angles, centres and radii, and for stop offsets outside 0 to 1
+    pub(crate) fn validate(&self) -> Result<(), QrError> {
+        let stops = match self {
+            Gradient::Linear { angle, stops } => {
+                if !angle.is_finite() {
+                    return Err(QrError::InvalidConfig(format!("gradient angle {angle} must be finite")));
+                }
+                stops
+            }
+            Gradient::Radial { centre: (cx, cy), radius, stops } => {
+                if !(cx.is_finite() && cy.is_finite()) {
+                    return Err(QrError::InvalidConfig(format!("gradient centre ({cx}, {cy}) must be finite")));
+                }
+                if !(radius.is_finite() && *radius >= 0.0) {
+                    return Err(QrError::InvalidConfig(format!("gradient radius {radius} must be finite and not negative")));
+                }
+                stops
+            }
+        };
+        match stops.iter().find(|stop| !(0.0..=1.0).contains(&stop.offset)) {
+            Some(stop) => Err(QrError::InvalidConfig(format!("gradient stop offset {} is outside 0 to 1", stop.offset))),
+            None => Ok(()),
+        }
+    }
+
+    /// Builds the SVG gradient element for a symbol at `origin` that is `size` pixels wide,
+    /// with coordinates and offsets rounded to `precision` decimal places
+    ///
+    /// Coordinates are in user space so every module samples the same gradient.
+    /// `transform` maps those coordinates into the user space of the element using
+    /// the gradient, for elements drawn inside a transformed group.
+    pub(crate) fn to_svg_definition(
+        &self,
+        id: &str,
+        origin: f32,
+        size: f32,
+        transform: Option<&str>,
+        precision: u8,
+    ) -> Result<String, QrError> {
+        self.validate()?;
+        let transform = transform
+            .map(|transform| format!(" gradientTransform='{transform}'"))
+            .unwrap_or_default();
+        match self {
+            Gradient::Linear { angle, stops } => {
+                // Stretch the gradient line so it reaches the symbol's corners at any angle
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let half_length = size / 2.0 * (sin.abs() + cos.abs());
+                let centre = origin + size / 2.0;
+                Ok(format!(
+                    "    <linearGradient id='{id}' gradientUnits='userSpaceOnUse' x1='{x1}' y1='{y1}' x2='{x2}' y2='{y2}'{transform}>\n{stops}    </linearGradient>\n",
+                    x1 = round(centre - cos * half_length, precision),
+                    y1 = round(centre - sin * half_length, precision),
+                    x2 = round(centre + cos * half_length, precision),
+                    y2 = round(centre + sin * half_length, precision),
+                    stops = render_stops(stops, precision),
+                ))
+            }
+            Gradient::Radial { centre: (cx, cy), radius, stops } => {
+                Ok(format!(
+                    "    <radialGradient id='{id}' gradientUnits='userSpaceOnUse' cx='{cx}' cy='{cy}' r='{r}'{transform}>\n{stops}    </radialGradient>\n",
+                    cx = round(origin + cx * size, precision),
+                    cy = round(origin + cy * size, precision),
+                    r = round(radius * size, precision),
+                    stops = render_stops(stops, precision),
+                ))
+            }
+        }
+    }
+}
+
+/// Renders the `<stop>` elements of a gradient
+fn render_stops(stops: &[GradientStop], precision: u8) -> String {
+    stops
+        .iter()
+        .map(|stop| {
+            format!(
+                "      <stop offset='{offset}' stop-color='{color}'/>\n",
+                offset = round(stop.offset, precision),
+                color = escape_xml(&stop.color.to_string()),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stops() -> Vec<GradientStop> {
+        vec![GradientStop::new(0.0, "red".parse().unwrap()), GradientStop::new(1.0, "blue".parse().unwrap())]
+    }
+
+    #[test]
+    fn spans_linear_gradient_across_symbol() {
+        let gradient = Gradient::Linear { angle: 0.0, stops: stops() };
+        let svg = gradient.to_svg_definition("g", 40.0, 250.0, None, 2).unwrap();
+        assert!(svg.contains("gradientUnits='userSpaceOnUse' x1='40' y1='165' x2='290' y2='165'"));
+        assert!(svg.contains("<stop offset='0' stop-color='#ff0000'/>"));
+        assert!(svg.contains("<stop offset='1' stop-color='#0000ff'/>"));
+
+        // A diagonal gradient reaches from corner to corner
+        let gradient = Gradient::Linear { angle: 45.0, stops: stops() };
+        let svg = gradient.to_svg_definition("g", 0.0, 100.0, None, 2).unwrap();
+        assert!(svg.contains("x1='0' y1='0' x2='100' y2='100'"), "{svg}");
+    }
+
+    #[test]
+    fn scales_radial_gradient_to_symbol() {
+        let gradient = Gradient::Radial { centre: (0.5, 0.5), radius: 0.75, stops: stops() };
+        let svg = gradient.to_svg_definition("g", 40.0, 200.0, Some("translate(-40, -40)"), 2).unwrap();
+        assert!(svg.contains("cx='140' cy='140' r='150' gradientTransform='translate(-40, -40)'"));
+    }
+
+    #[test]
+    fn rounds_to_precision() {
+        let stops = vec![GradientStop::new(1.0 / 3.0, "red".parse().unwrap())];
+        let svg = Gradient::Linear { angle: 30.0, stops: stops.clone() }.to_svg_definition("g", 0.0, 100.0, None, 2).unwrap();
+        assert!(svg.contains("x1='-9.15' y1='15.85' x2='109.15' y2='84.15'"), "{svg}");
+        assert!(svg.contains("<stop offset='0.33' stop-color='#ff0000'/>"));
+        let svg = Gradient::Radial { centre: (1.0 / 3.0, 0.5), radius: 0.1234, stops }.to_svg_definition("g", 0.0, 10.0, None, 1).unwrap();
+        assert!(svg.contains("cx='3.3' cy='5' r='1.2'"), "{svg}");
+    }
+
+    #[test]
+    fn refuses_invalid_values() {
+        let stop = |offset: f32| vec![GradientStop::new(offset, "red".parse().unwrap())];
+        for gradient in [
+            Gradient::Linear { angle: f32::NAN, stops: stops() },
+            Gradient::Linear { angle: f32::INFINITY, stops: stops() },
+            Gradient::Linear { angle: 0.0, stops: stop(-0.5) },
+            Gradient::Linear { angle: 0.0, stops: stop(2.0) },
+            Gradient::Linear { angle: 0.0, stops: stop(f32::NAN) },
+            Gradient::Radial { centre: (0.5, 0.5), radius: f32::INFINITY, stops: stops() },
+            Gradient::Radial { centre: (0.5, 0.5), radius: -1.0, stops: stops() },
+            Gradient::Radial { centre: (f32::NAN, 0.5), radius: 0.5, stops: stops() },
+        ] {
+            assert!(matches!(gradient.to_svg_definition("g", 0.0, 10.0, None, 2), Err(QrError::InvalidConfig(_))), "{gradient:?}");
+        }
+    }
+}
@@ -8,9 +8,51 @@ pub fn hello() -> String {
 
 mod svg_rendering;
 mod format_conversion;
+mod encoding;
+mod error;
+mod mask;
+mod qr_matrix;
+mod gradient;
+mod ec_blocks;
+mod logo;
+mod outline;
+mod module_shape;
+mod unit_path;
+mod contrast;
+mod color;
+#[cfg(feature = "verify")]
+mod reed_solomon;
+#[cfg(feature = "verify")]
+mod perspective;
+#[cfg(feature = "verify")]
+mod detection;
+#[cfg(feature = "verify")]
+mod decoding;
+#[cfg(feature = "verify")]
+mod verification;
+#[cfg(feature = "verify")]
+mod scannability;
+#[cfg(feature = "verify")]
+mod error_budget;
 
-pub use svg_rendering::{render_qr_matrix_as_svg, DataShape, FinderShape, QrRenderConfig, FinderStyle, DataStyle};
-pub use format_conversion::{RasterFormat, FormatConversionError, QrCodeOutput, QrCodeResult};
+pub use svg_rendering::{render_qr_matrix_as_svg, Background, DataShape, DataRenderMode, FinderShape, AlignmentShape, QrRenderConfig, FinderStyle, DataStyle, AlignmentStyle, SvgSize, DEFAULT_MODULE_SIZE, DEFAULT_PRECISION, DEFAULT_QUIET_ZONE};
+pub use encoding::{ErrorCorrectionLevel, QrEncodeOptions, VersionConstraint, MaskSelection, MIN_VERSION, MAX_VERSION};
+pub use mask::MASK_PATTERN_COUNT;
+pub use qr_matrix::{QrMatrix, Module, ModuleRole};
+pub use format_conversion::{RasterFormat, QrCodeOutput, QrCodeResult};
+pub use error::QrError;
+pub use color::Color;
+#[cfg(feature = "verify")]
+pub use verification::decode_raster;
+#[cfg(feature = "verify")]
+pub use scannability::{Degradation, ScannabilityReport, ScenarioResult};
+#[cfg(feature = "verify")]
+pub use error_budget::{BlockErrors, ErrorBudgetReport};
+pub use gradient::{Gradient, GradientStop};
+pub use logo::{Logo, LogoImage};
+pub use module_shape::{FinderContext, FinderPatternShape, ModuleContext, ModuleShape, Neighbours};
+pub use unit_path::UnitPath;
+pub use contrast::{ContrastIssue, ContrastProblem, ContrastSeverity, MIN_CONTRAST_RATIO, RECOMMENDED_CONTRAST_RATIO};
 
 /// Renders a QR code in the specified format
 /// 
@@ -27,15 +69,40 @@ pub fn render_qr_code(
     config: Option<&QrRenderConfig>,
     format: RasterFormat,
     size: u32,
-) -> Result<QrCodeResult, FormatConversionError> {
+) -> Result<QrCodeResult, QrError> {
+    let default_config = QrRenderConfig::default();
+    let encoding = &config.unwrap_or(&default_config).encoding;
+
     // Generate the QR code matrix
-    let matrix = generate_qr_matrix(data);
-    
-    // Render the matrix as SVG
-    let svg = render_qr_matrix_as_svg(&matrix, config);
+    let code = encoding::encode_qr_code(data, encoding)?;
+    let matrix = QrMatrix::from_encoded(&code);
+
+    check_config(&matrix, config.unwrap_or(&default_config))?;
+
+    // Render the matrix as SVG, sized in pixels so it fills the raster
+    let (svg, module_size) = svg_rendering::render_qr_matrix_for_raster(&matrix, config, size);
     
     // Convert the SVG to the requested format
-    format_conversion::convert_svg_to_format(&svg, format, size)
+    let output = format_conversion::convert_svg_to_format(&svg, format, size, config.unwrap_or(&default_config).matte)?;
+
+    let result = QrCodeResult {
+        data: output,
+        width: size,
+        height: size,
+        module_size,
+        physical_width_mm: None,
+        physical_height_mm: None,
+        inverted: config.is_some_and(|config| config.inverted),
+        error_correction: code.error_correction,
+        version: code.version,
+        mask: code.mask,
+        mask_penalties: code.mask_penalties,
+    };
+    #[cfg(feature = "verify")]
+    if config.is_some_and(|config| config.verify) {
+        result.verify(data)?;
+    }
+    Ok(result)
 }
 
 /// Renders a QR code as SVG
@@ -45,59 +112,102 @@ pub fn render_qr_code(
 /// * `config` - Optional rendering configuration
 /// 
 /// # Returns
-/// * `QrCodeResult` - The rendered QR code result
+/// * `Result<QrCodeResult>` - The rendered QR code result
 pub fn render_qr_code_svg(
     data: &str,
     config: Option<&QrRenderConfig>,
-) -> QrCodeResult {
+) -> Result<QrCodeResult, QrError> {
+    let default_config = QrRenderConfig::default();
+    let encoding = &config.unwrap_or(&default_config).encoding;
+
     // Generate the QR code matrix
-    let matrix = generate_qr_matrix(data);
-    
+    let code = encoding::encode_qr_code(data, encoding)?;
+    let matrix = QrMatrix::from_encoded(&code);
+
+    check_config(&matrix, config.unwrap_or(&default_config))?;
+
     // Render the matrix as SVG
     let svg = render_qr_matrix_as_svg(&matrix, config);
     
-    // Calculate size based on the matrix dimensions
-    let size = (matrix.len() * 10) as u32; // 10 pixels per module
+    // Calculate size based on the matrix dimensions plus the quiet zone on each side
+    let config = config.unwrap_or(&default_config);
+    let size = ((matrix.width() + 2 * config.quiet_zone) as f32 * config.module_size).round() as u32;
+    let physical_size = config.svg_size.millimetres();
     
-    QrCodeResult {
+    let result = QrCodeResult {
         data: QrCodeOutput::Svg(svg),
         width: size,
         height: size,
+        module_size: config.module_size,
+        physical_width_mm: physical_size,
+        physical_height_mm: physical_size,
+        inverted: config.inverted,
+        error_correction: code.error_correction,
+        version: code.version,
+        mask: code.mask,
+        mask_penalties: code.mask_penalties,
+    };
+    #[cfg(feature = "verify")]
+    if config.verify {
+        result.verify(data)?;
     }
+    Ok(result)
 }
 
-// Generates a QR code matrix for input data (https://google.com)
-// Returns a 2D vector of booleans, where true = dark module, false = light module
-pub fn generate_qr_matrix(data_to_encode: &str) -> Vec<Vec<bool>> {
-    // Import the qrcode crate
-    use qrcode::QrCode;
-
-    // The data to encode
-    let url = data_to_encode;
-
-    // Create the QR code (default error correction)
-    let code = QrCode::new(url).expect("Failed to generate QR code");
-
-    // Convert the QR code into a boolean matrix
-    // Each module (pixel) is true (dark) or false (light)
-    let width = code.width();
-    let mut matrix = Vec::with_capacity(width);
-    for y in 0..width {
-        let mut row = Vec::with_capacity(width);
-        for x in 0..width {
-            // Convert Color to bool: true = dark, false = light
-            let color = code[(x, y)];
-            row.push(matches!(color, qrcode::Color::Dark));
-        }
-        matrix.push(row);
+/// Refuses configurations that would render a broken or unreadable code
+fn check_config(matrix: &QrMatrix, config: &QrRenderConfig) -> Result<(), QrError> {
+    if !(config.module_size.is_finite() && config.module_size > 0.0) {
+        return Err(QrError::InvalidConfig(format!("module size {} must be positive", config.module_size)));
+    }
+    config.svg_size.validate()?;
+    let id_character = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    if !config.id_prefix.starts_with(|c: char| c.is_ascii_alphabetic()) || !config.id_prefix.chars().all(id_character) {
+        return Err(QrError::InvalidConfig(format!(
+            "id prefix '{}' must start with a letter and hold only letters, digits, '-' and '_'",
+            config.id_prefix
+        )));
+    }
+    for gradient in config.gradients() {
+        gradient.validate()?;
+    }
+    if let Background::Image(image) = &config.background {
+        image.data_uri()?;
     }
-    matrix
+    // Refuse logos that would make the code unreadable rather than rendering a broken code
+    if let Some(logo) = &config.logo {
+        logo::validate_logo(matrix, logo)?;
+    }
+    if config.verify && !cfg!(feature = "verify") {
+        return Err(QrError::InvalidConfig("verify needs qrcode-core built with the `verify` feature".to_string()));
+    }
+    Ok(())
+}
+
+// Generates a QR code matrix for input data (https://google.com)
+// Returns a QrMatrix describing every module's colour and role; use `to_bool_matrix`
+// for the legacy grid of booleans, where true = dark module, false = light module
+// Fails when the data does not fit within the requested version bounds
+pub fn generate_qr_matrix(data_to_encode: &str, options: &QrEncodeOptions) -> Result<QrMatrix, QrError> {
+    // Create the QR code at the requested error correction level and version
+    let code = encoding::encode_qr_code(data_to_encode, options)?;
+    Ok(QrMatrix::from_encoded(&code))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Rasterizes `data` with `config`, asserts the image decodes back to it and returns the image
+    #[cfg(feature = "verify")]
+    fn assert_decodes(config: &QrRenderConfig, data: &str, format: RasterFormat, size: u32) -> Vec<u8> {
+        let result = render_qr_code(data, Some(config), format, size).unwrap();
+        let QrCodeOutput::Raster(raster, _) = result.data else {
+            panic!("Expected Raster output");
+        };
+        assert_eq!(decode_raster(&raster, false), Ok(data.to_string()));
+        raster
+    }
+
     #[test]
     fn it_works() {
         let result = add(2, 2);
@@ -107,7 +217,7 @@ mod tests {
 
     #[test]
     fn generates_qr_matrix() {
-        let matrix = generate_qr_matrix("https://jedi.org");
+        let matrix = generate_qr_matrix("https://jedi.org", &QrEncodeOptions::default()).unwrap().to_bool_matrix();
         // The matrix should be square and non-empty
         assert!(!matrix.is_empty());
         let width = matrix.len();
@@ -163,7 +273,7 @@ mod tests {
 
     #[test]
     fn renders_qr_code_as_svg() {
-        let result = render_qr_code_svg("https://jedi.org", None);
+        let result = render_qr_code_svg("https://jedi.org", None).unwrap();
         match result.data {
             QrCodeOutput::Svg(svg) => {
                 assert!(svg.starts_with("<svg"));
@@ -171,10 +281,439 @@ mod tests {
             }
             _ => panic!("Expected SVG output"),
         }
-        // Size should be based on matrix dimensions (modules * 10 pixels)
-        let matrix = generate_qr_matrix("https://jedi.org");
-        let expected_size = (matrix.len() * 10) as u32;
+        // Size should be based on matrix dimensions plus the quiet zone ((modules + 2 * 4) * 10 pixels)
+        let matrix = generate_qr_matrix("https://jedi.org", &QrEncodeOptions::default()).unwrap();
+        let expected_size = ((matrix.width() + 2 * DEFAULT_QUIET_ZONE) * 10) as u32;
         assert_eq!(result.width, expected_size);
         assert_eq!(result.height, expected_size);
     }
+
+    #[test]
+    fn applies_requested_error_correction_level() {
+        let low = QrEncodeOptions { error_correction: ErrorCorrectionLevel::Low, ..QrEncodeOptions::default() };
+        let high = QrEncodeOptions { error_correction: ErrorCorrectionLevel::High, ..QrEncodeOptions::default() };
+        let data = "https://jedi.org/the-high-ground";
+        // Higher error correction needs more codewords, so the symbol grows
+        assert!(generate_qr_matrix(data, &high).unwrap().width() > generate_qr_matrix(data, &low).unwrap().width());
+
+        let config = QrRenderConfig {
+            encoding: high,
+            ..QrRenderConfig::default()
+        };
+        let result = render_qr_code_svg(data, Some(&config)).unwrap();
+        assert_eq!(result.error_correction, ErrorCorrectionLevel::High);
+
+        let result = render_qr_code(data, Some(&config), RasterFormat::Png, 100).unwrap();
+        assert_eq!(result.error_correction, ErrorCorrectionLevel::High);
+
+        let result = render_qr_code_svg(data, None).unwrap();
+        assert_eq!(result.error_correction, ErrorCorrectionLevel::Medium);
+    }
+
+    #[test]
+    fn reports_requested_version() {
+        let config = QrRenderConfig {
+            encoding: QrEncodeOptions { version: VersionConstraint::Fixed(5), ..QrEncodeOptions::default() },
+            ..QrRenderConfig::default()
+        };
+        let result = render_qr_code_svg("https://jedi.org", Some(&config)).unwrap();
+        assert_eq!(result.version, 5);
+        // Version 5 is 37 modules wide plus 8 quiet zone modules, 10 pixels per module
+        assert_eq!(result.width, 450);
+
+        let result = render_qr_code("https://jedi.org", Some(&config), RasterFormat::Png, 100).unwrap();
+        assert_eq!(result.version, 5);
+    }
+
+    #[cfg(feature = "verify")]
+    #[test]
+    fn reports_pixel_and_physical_dimensions() {
+        let config = QrRenderConfig {
+            encoding: QrEncodeOptions { version: VersionConstraint::Fixed(5), ..QrEncodeOptions::default() },
+            module_size: 4.0,
+            svg_size: SvgSize::Inches(2.0),
+            ..QrRenderConfig::default()
+        };
+        // 45 modules of 4 pixels, printed 2 inches wide
+        let result = render_qr_code_svg("https://jedi.org", Some(&config)).unwrap();
+        assert_eq!((result.width, result.height, result.module_size), (180, 180, 4.0));
+        assert_eq!(result.physical_width_mm, Some(50.8));
+        assert_eq!(result.physical_height_mm, Some(50.8));
+        let QrCodeOutput::Svg(svg) = result.data else {
+            panic!("Expected SVG output");
+        };
+        assert!(svg.contains("width='2in' height='2in' viewBox='0 0 180 180'"));
+
+        // Raster output fills the requested size whatever size the SVG declares
+        let data = "https://jedi.org/print";
+        let modules = generate_qr_matrix(data, &QrEncodeOptions::default()).unwrap().width() + 2 * DEFAULT_QUIET_ZONE;
+        for svg_size in [SvgSize::Millimetres(30.0), SvgSize::ViewBoxOnly, SvgSize::Percent(50.0)] {
+            let config = QrRenderConfig { svg_size, data_styling: DataStyle::color("black").unwrap(), ..QrRenderConfig::default() };
+            let result = render_qr_code(data, Some(&config), RasterFormat::Png, 300).unwrap();
+            assert_eq!((result.width, result.physical_width_mm), (300, None));
+            assert_eq!(result.module_size, 300.0 / modules as f32);
+            assert_decodes(&config, data, RasterFormat::Png, 300);
+        }
+    }
+
+    #[test]
+    fn refuses_invalid_dimensions() {
+        for config in [
+            QrRenderConfig { module_size: 0.0, ..QrRenderConfig::default() },
+            QrRenderConfig { module_size: f32::INFINITY, ..QrRenderConfig::default() },
+            QrRenderConfig { svg_size: SvgSize::Millimetres(0.0), ..QrRenderConfig::default() },
+            QrRenderConfig { svg_size: SvgSize::Percent(f32::NAN), ..QrRenderConfig::default() },
+        ] {
+            assert!(matches!(render_qr_code_svg("https://jedi.org", Some(&config)), Err(QrError::InvalidConfig(_))));
+        }
+    }
+
+    #[test]
+    fn refuses_invalid_gradients() {
+        let stops = vec![GradientStop::new(0.0, "red".parse().unwrap()), GradientStop::new(1.5, "blue".parse().unwrap())];
+        for config in [
+            QrRenderConfig { data_styling: DataStyle::Gradient(Gradient::Linear { angle: 0.0, stops }), ..QrRenderConfig::default() },
+            QrRenderConfig {
+                background: Background::Gradient(Gradient::Linear { angle: f32::NAN, stops: Vec::new() }),
+                ..QrRenderConfig::default()
+            },
+        ] {
+            assert!(matches!(render_qr_code_svg("https://jedi.org", Some(&config)), Err(QrError::InvalidConfig(_))));
+            // Rendering the matrix directly cannot fail, so the gradient is replaced by a solid color
+            let matrix = generate_qr_matrix("https://jedi.org", &config.encoding).unwrap();
+            assert!(!render_qr_matrix_as_svg(&matrix, Some(&config)).contains("Gradient"));
+        }
+    }
+
+    #[test]
+    fn refuses_invalid_id_prefixes() {
+        for id_prefix in ["", "1qr", "qr code", "qr'/><script>"] {
+            let config = QrRenderConfig { id_prefix: id_prefix.to_string(), ..QrRenderConfig::default() };
+            assert!(matches!(render_qr_code_svg("https://jedi.org", Some(&config)), Err(QrError::InvalidConfig(_))));
+        }
+        let config = QrRenderConfig { id_prefix: "menu_qr-2".to_string(), ..QrRenderConfig::default() };
+        assert!(render_qr_code_svg("https://jedi.org", Some(&config)).is_ok());
+    }
+
+    #[test]
+    fn fails_when_data_exceeds_version_bounds() {
+        let config = QrRenderConfig {
+            encoding: QrEncodeOptions { version: VersionConstraint::Range { min: 1, max: 2 }, ..QrEncodeOptions::default() },
+            ..QrRenderConfig::default()
+        };
+        let data = "https://jedi.org/".repeat(10);
+        assert!(render_qr_code_svg(&data, Some(&config)).is_err());
+        assert!(matches!(
+            render_qr_code(&data, Some(&config), RasterFormat::Png, 100),
+            Err(QrError::DataTooLong { max_version: 2 })
+        ));
+    }
+
+    #[test]
+    fn reports_mask_and_penalties() {
+        let config = QrRenderConfig {
+            encoding: QrEncodeOptions { mask: MaskSelection::Fixed(3), ..QrEncodeOptions::default() },
+            ..QrRenderConfig::default()
+        };
+        let result = render_qr_code_svg("https://jedi.org", Some(&config)).unwrap();
+        assert_eq!(result.mask, 3);
+        assert_eq!(result.mask_penalties.len(), MASK_PATTERN_COUNT);
+
+        let result = render_qr_code_svg("https://jedi.org", None).unwrap();
+        assert_eq!(result.mask_penalties[result.mask as usize], *result.mask_penalties.iter().min().unwrap());
+    }
+
+    #[cfg(feature = "verify")]
+    #[test]
+    fn triangle_finders_scan() {
+        let data = "https://jedi.org/triangles";
+        let config = QrRenderConfig {
+            finder_frame_shape: FinderShape::Triangle,
+            finder_eye_shape: FinderShape::Triangle,
+            finder_frame_styling: FinderStyle::color("black").unwrap(),
+            finder_eye_styling: FinderStyle::color("black").unwrap(),
+            data_shape: DataShape::Square,
+            data_styling: DataStyle::color("black").unwrap(),
+            ..QrRenderConfig::default()
+        };
+        assert_decodes(&config, data, RasterFormat::Png, 400);
+    }
+
+    #[cfg(feature = "verify")]
+    #[test]
+    fn gradients_survive_rasterisation() {
+        let data = "https://jedi.org/gradients";
+        let stops = vec![GradientStop::new(0.0, "#000080".parse().unwrap()), GradientStop::new(1.0, "#800000".parse().unwrap())];
+        let config = QrRenderConfig {
+            finder_frame_styling: FinderStyle::Gradient(Gradient::Linear { angle: 0.0, stops: stops.clone() }),
+            finder_eye_styling: FinderStyle::Gradient(Gradient::Linear { angle: 0.0, stops: stops.clone() }),
+            data_shape: DataShape::Square,
+            data_styling: DataStyle::Gradient(Gradient::Linear { angle: 0.0, stops }),
+            ..QrRenderConfig::default()
+        };
+        let png = assert_decodes(&config, data, RasterFormat::Png, 330);
+
+        // The left finder frame starts blue and the right one ends red, as one continuous gradient
+        let image = image::load_from_memory(&png).unwrap().to_rgb8();
+        let left = image.get_pixel(41, 60).0;
+        let right = image.get_pixel(288, 60).0;
+        assert!(left[2] > 100 && left[0] < 30, "{left:?}");
+        assert!(right[0] > 100 && right[2] < 30, "{right:?}");
+    }
+
+    fn solid_png(color: [u8; 3]) -> Vec<u8> {
+        let logo = image::RgbImage::from_pixel(16, 16, image::Rgb(color));
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::DynamicImage::ImageRgb8(logo).write_to(&mut png, image::ImageOutputFormat::Png).unwrap();
+        png.into_inner()
+    }
+
+    #[cfg(feature = "verify")]
+    #[test]
+    fn embeds_logos_in_raster_output() {
+        let data = "https://jedi.org/logos";
+        let svg_logo = "<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 10 10'><rect width='10' height='10' fill='#ff00ff'/></svg>";
+        let svg_fragment = "<circle cx='5' cy='5' r='5' fill='#ff00ff'/>";
+        for image in [
+            LogoImage::Raster(solid_png([255, 0, 255])),
+            LogoImage::Svg(svg_logo.to_string()),
+            LogoImage::Svg(svg_fragment.to_string()),
+        ] {
+            let mut logo = Logo::new(image, 0.2);
+            logo.plate = Some("white".parse().unwrap());
+            let config = QrRenderConfig {
+                encoding: QrEncodeOptions { error_correction: ErrorCorrectionLevel::High, ..QrEncodeOptions::default() },
+                data_shape: DataShape::Square,
+                data_styling: DataStyle::color("black").unwrap(),
+                logo: Some(logo),
+                ..QrRenderConfig::default()
+            };
+            let png = assert_decodes(&config, data, RasterFormat::Png, 400);
+            let image = image::load_from_memory(&png).unwrap().to_rgb8();
+            assert_eq!(image.get_pixel(200, 200).0, [255, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn refuses_logos_the_error_correction_cannot_recover() {
+        let config = QrRenderConfig {
+            encoding: QrEncodeOptions {
+                error_correction: ErrorCorrectionLevel::Low,
+                version: VersionConstraint::Fixed(5),
+                ..QrEncodeOptions::default()
+            },
+            logo: Some(Logo::new(LogoImage::Raster(solid_png([0, 0, 0])), 0.2)),
+            ..QrRenderConfig::default()
+        };
+        let result = render_qr_code_svg("https://jedi.org/logos", Some(&config));
+        assert!(matches!(result, Err(QrError::LogoTooLarge { .. })));
+
+        // The lower level renderer draws it anyway, leaving the decision to the caller
+        let matrix = generate_qr_matrix("https://jedi.org/logos", &config.encoding).unwrap();
+        assert!(render_qr_matrix_as_svg(&matrix, Some(&config)).contains("data:image/png;base64,"));
+    }
+
+    #[cfg(feature = "verify")]
+    #[test]
+    fn liquid_data_still_scans() {
+        let data = "https://jedi.org/liquid";
+        for data_render_mode in [DataRenderMode::MergedPath, DataRenderMode::PerModule] {
+            let config = QrRenderConfig {
+                data_shape: DataShape::Liquid,
+                data_render_mode,
+                data_styling: DataStyle::color("black").unwrap(),
+                ..QrRenderConfig::default()
+            };
+            assert_decodes(&config, data, RasterFormat::Png, 400);
+        }
+    }
+
+    #[cfg(feature = "verify")]
+    #[test]
+    fn line_data_shapes_still_scan() {
+        let data = "https://jedi.org/lines";
+        for data_shape in [DataShape::HorizontalLines { thickness: 0.8 }, DataShape::VerticalLines { thickness: 0.8 }] {
+            let config = QrRenderConfig {
+                data_shape,
+                data_styling: DataStyle::color("black").unwrap(),
+                ..QrRenderConfig::default()
+            };
+            for format in [RasterFormat::Png, RasterFormat::Jpeg] {
+                assert_decodes(&config, data, format, 400);
+            }
+        }
+    }
+
+    #[cfg(feature = "verify")]
+    #[test]
+    fn custom_shapes_still_scan() {
+        struct Diamond;
+        impl ModuleShape for Diamond {
+            fn render(&self, module: &ModuleContext, fill: &str) -> String {
+                let half = module.size / 2.0;
+                format!("<path d='M{},{} l{half},{half} l-{half},{half} l-{half},-{half} Z' fill='{fill}'/>", module.px + half, module.py)
+            }
+        }
+        struct Outline;
+        impl FinderPatternShape for Outline {
+            fn render_frame(&self, finder: &FinderContext, fill: &str) -> String {
+                let size = finder.module_size;
+                format!("<path d='M0,0 h{0} v{0} h-{0} Z M{1},{1} v{2} h{2} v-{2} Z' fill='{fill}'/>", 7.0 * size, size, 5.0 * size)
+            }
+            fn render_eye(&self, finder: &FinderContext, fill: &str) -> String {
+                let size = finder.module_size;
+                format!("<rect x='{0}' y='{0}' width='{1}' height='{1}' fill='{fill}'/>", 2.0 * size, 3.0 * size)
+            }
+        }
+
+        let data = "https://jedi.org/custom";
+        let config = QrRenderConfig {
+            data_shape: DataShape::Custom(Box::new(Diamond)),
+            finder_frame_shape: FinderShape::Custom(Box::new(Outline)),
+            finder_eye_shape: FinderShape::Custom(Box::new(Outline)),
+            data_styling: DataStyle::color("black").unwrap(),
+            ..QrRenderConfig::default()
+        };
+        assert_decodes(&config, data, RasterFormat::Png, 400);
+    }
+
+    #[cfg(feature = "verify")]
+    #[test]
+    fn custom_paths_still_scan() {
+        let data = "https://jedi.org/paths";
+        let squircle = "M0.5,0 C0.95,0 1,0.05 1,0.5 S0.95,1 0.5,1 S0,0.95 0,0.5 S0.05,0 0.5,0 Z";
+        let config = QrRenderConfig {
+            data_shape: DataShape::CustomPath(UnitPath::parse(squircle).unwrap()),
+            // A square ring one module thick, wound the same way as its outside
+            finder_frame_shape: FinderShape::CustomPath(UnitPath::parse("M0,0 H1 V1 H0 Z M0.142857,0.142857 H0.857143 V0.857143 H0.142857 Z").unwrap()),
+            finder_eye_shape: FinderShape::CustomPath(UnitPath::parse(squircle).unwrap()),
+            data_styling: DataStyle::color("black").unwrap(),
+            ..QrRenderConfig::default()
+        };
+        assert_decodes(&config, data, RasterFormat::Png, 400);
+    }
+
+    #[cfg(feature = "verify")]
+    #[test]
+    fn snaps_modules_to_whole_pixels() {
+        let data = "https://jedi.org/snap";
+        // Counts pixels that are neither clearly dark nor clearly light, i.e. blurred module edges
+        let blurred = |snap_to_pixels| {
+            let config = QrRenderConfig {
+                data_shape: DataShape::Square,
+                data_styling: DataStyle::color("black").unwrap(),
+                finder_frame_styling: FinderStyle::color("black").unwrap(),
+                finder_eye_styling: FinderStyle::color("black").unwrap(),
+                alignment_styling: AlignmentStyle::color("black").unwrap(),
+                snap_to_pixels,
+                ..QrRenderConfig::default()
+            };
+            let raster = assert_decodes(&config, data, RasterFormat::Png, 250);
+            let image = image::load_from_memory(&raster).unwrap().to_luma8();
+            let grey = image.pixels().filter(|pixel| (48..208).contains(&pixel.0[0])).count();
+            (grey, render_qr_code(data, Some(&config), RasterFormat::Png, 250).unwrap().module_size)
+        };
+        let (smooth, fractional) = blurred(false);
+        let (sharp, whole) = blurred(true);
+        assert_eq!(whole, whole.floor());
+        assert!(fractional > whole, "{fractional} vs {whole}");
+        assert!(sharp * 10 < smooth, "{sharp} grey pixels with snapping, {smooth} without");
+    }
+
+    #[cfg(feature = "verify")]
+    #[test]
+    fn renders_transparent_backgrounds() {
+        let data = "https://jedi.org/glass";
+        let config = QrRenderConfig {
+            background: Background::None,
+            matte: "yellow".parse().unwrap(),
+            ..QrRenderConfig::default()
+        };
+
+        let result = render_qr_code(data, Some(&config), RasterFormat::Png, 300).unwrap();
+        let QrCodeOutput::Raster(png, _) = result.data else {
+            panic!("Expected Raster output");
+        };
+        let image = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(image.get_pixel(2, 2).0[3], 0, "quiet zone should be transparent");
+
+        // JPEG has no alpha, so the quiet zone takes the matte color and the code still scans
+        let jpeg = assert_decodes(&config, data, RasterFormat::Jpeg, 300);
+        let [red, green, blue] = image::load_from_memory(&jpeg).unwrap().to_rgb8().get_pixel(2, 2).0;
+        assert!(red > 240 && green > 240 && blue < 16, "{red} {green} {blue}");
+    }
+
+    #[test]
+    fn refuses_unusable_backgrounds() {
+        let config = QrRenderConfig {
+            background: Background::Image(LogoImage::Raster(b"GIF89a".to_vec())),
+            ..QrRenderConfig::default()
+        };
+        assert!(matches!(render_qr_code_svg("https://sith.org", Some(&config)), Err(QrError::InvalidConfig(_))));
+
+        let config = QrRenderConfig { matte: "transparent".parse().unwrap(), ..QrRenderConfig::default() };
+        let result = render_qr_code("https://sith.org", Some(&config), RasterFormat::Jpeg, 100);
+        assert!(matches!(result, Err(QrError::InvalidConfig(_))));
+    }
+
+    #[cfg(feature = "verify")]
+    #[test]
+    fn renders_inverted_codes() {
+        let data = "https://jedi.org/dark-side";
+        let config = QrRenderConfig {
+            inverted: true,
+            data_styling: DataStyle::color("#111").unwrap(),
+            background: Background::color("white").unwrap(),
+            ..QrRenderConfig::default()
+        };
+        let result = render_qr_code(data, Some(&config), RasterFormat::Png, 300).unwrap();
+        assert!(result.inverted);
+        let QrCodeOutput::Raster(png, _) = result.data else {
+            panic!("Expected Raster output");
+        };
+        let image = image::load_from_memory(&png).unwrap().to_luma8();
+        // The quiet zone is dark, and the code only reads once inverted back to dark on light
+        assert!(image.get_pixel(2, 2).0[0] < 32);
+        assert!(decode_raster(&png, false).is_err());
+        assert_eq!(decode_raster(&png, true), Ok(data.to_string()));
+
+        assert!(render_qr_code_svg(data, Some(&config)).unwrap().inverted);
+        assert!(!render_qr_code_svg(data, None).unwrap().inverted);
+    }
+
+    #[cfg(feature = "verify")]
+    #[test]
+    fn verifies_output_on_request() {
+        let data = "https://jedi.org/checked";
+        let styled = QrRenderConfig {
+            data_shape: DataShape::Liquid,
+            alignment_shape: AlignmentShape::Dot,
+            verify: true,
+            ..QrRenderConfig::default()
+        };
+        assert!(render_qr_code(data, Some(&styled), RasterFormat::Png, 300).is_ok());
+        assert!(render_qr_code_svg(data, Some(&styled)).is_ok());
+
+        // Invisible data modules render without complaint unless verification is requested
+        let invisible = QrRenderConfig {
+            data_styling: DataStyle::color("white").unwrap(),
+            ..QrRenderConfig::default()
+        };
+        assert!(render_qr_code(data, Some(&invisible), RasterFormat::Png, 300).is_ok());
+        let invisible = QrRenderConfig { verify: true, ..invisible };
+        let result = render_qr_code(data, Some(&invisible), RasterFormat::Png, 300);
+        assert!(matches!(result, Err(QrError::Verification(_))), "{result:?}");
+        assert!(matches!(render_qr_code_svg(data, Some(&invisible)), Err(QrError::Verification(_))));
+
+        // Inverted codes are inverted back before decoding
+        let inverted = QrRenderConfig { inverted: true, verify: true, ..QrRenderConfig::default() };
+        assert!(render_qr_code(data, Some(&inverted), RasterFormat::Jpeg, 300).is_ok());
+    }
+
+    #[cfg(not(feature = "verify"))]
+    #[test]
+    fn refuses_verification_without_a_decoder() {
+        let config = QrRenderConfig { verify: true, ..QrRenderConfig::default() };
+        assert!(matches!(render_qr_code_svg("https://jedi.org", Some(&config)), Err(QrError::InvalidConfig(_))));
+    }
 }
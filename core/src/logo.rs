@@ -0,0 +1,234 @@
+use std::borrow::Cow;
+use base64::Engine;
+use usvg::{NodeExt, Options, Tree, TreeParsing};
+use crate::color::Color;
+use crate::ec_blocks::{codeword_blocks, correctable_errors_per_block, rs_blocks};
+use crate::error::QrError;
+use crate::qr_matrix::{data_module_positions, QrMatrix};
+
+/// Image placed in the centre of the symbol, or behind it as a `Background`
+pub enum LogoImage {
+    /// A complete SVG document, or a fragment of SVG elements, which is scaled to fit by the
+    /// bounding box of what it draws
+    Svg(String),
+    /// Encoded PNG or JPEG bytes
+    Raster(Vec<u8>),
+}
+
+/// A logo embedded in the centre of the symbol, over a cleared area of modules
+pub struct Logo {
+    pub image: LogoImage,
+    /// Width and height of the logo as a fraction of the symbol, excluding the quiet zone
+    pub size: f32,
+    /// Light margin kept clear around the logo, in modules
+    pub padding: usize,
+    /// Color of a plate drawn behind the logo and its padding
+    /// Without a plate the cleared area shows the background
+    pub plate: Option<Color>,
+}
+
+impl Logo {
+    /// Creates a logo covering `size` of the symbol, with a 1 module padding and no plate
+    pub fn new(image: LogoImage, size: f32) -> Self {
+        Logo { image, size, padding: 1, plate: None }
+    }
+
+    /// Returns the image as a data URI for an SVG `<image>` element
+    pub(crate) fn data_uri(&self) -> Result<String, QrError> {
+        self.image.data_uri()
+    }
+}
+
+impl LogoImage {
+    /// Returns the MIME type of the image, failing for raster formats other than PNG and JPEG
+    fn mime_type(&self) -> Result<&'static str, QrError> {
+        match self {
+            LogoImage::Svg(_) => Ok("image/svg+xml"),
+            LogoImage::Raster(bytes) => match image::guess_format(bytes) {
+                Ok(image::ImageFormat::Png) => Ok("image/png"),
+                Ok(image::ImageFormat::Jpeg) => Ok("image/jpeg"),
+                _ => Err(QrError::InvalidConfig("image must be a PNG or JPEG".to_string())),
+            },
+        }
+    }
+
+    /// Returns the image as a data URI for an SVG `<image>` element
+    pub(crate) fn data_uri(&self) -> Result<String, QrError> {
+        let encoded = match self {
+            LogoImage::Svg(svg) => base64::engine::general_purpose::STANDARD.encode(svg_document(svg)?.as_bytes()),
+            LogoImage::Raster(bytes) => base64::engine::general_purpose::STANDARD.encode(bytes),
+        };
+        Ok(format!("data:{};base64,{encoded}", self.mime_type()?))
+    }
+}
+
+/// Returns an SVG logo as a standalone document, wrapping a fragment in an `<svg>` root
+/// whose viewBox is the fragment's bounding box, since an image needs a root element and a
+/// fragment has no size of its own
+fn svg_document(svg: &str) -> Result<Cow<'_, str>, QrError> {
+    let start = svg.trim_start();
+    if start.starts_with("<svg") || start.starts_with("<?xml") || start.starts_with("<!") {
+        return Ok(Cow::Borrowed(svg));
+    }
+    let root = "<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink'";
+    let tree = Tree::from_str(&format!("{root}>{svg}</svg>"), &Options::default())
+        .map_err(|error| QrError::InvalidConfig(format!("SVG logo fragment cannot be parsed: {error}")))?;
+    let bounds = tree.root.calculate_bbox().filter(|bounds| bounds.width() > 0.0 && bounds.height() > 0.0);
+    let Some(bounds) = bounds else {
+        return Err(QrError::InvalidConfig("SVG logo fragment draws nothing".to_string()));
+    };
+    Ok(Cow::Owned(format!(
+        "{root} viewBox='{} {} {} {}'>{svg}</svg>",
+        bounds.x(),
+        bounds.y(),
+        bounds.width(),
+        bounds.height()
+    )))
+}
+
+/// Square block of modules in the centre of the symbol that is cleared for a logo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LogoArea {
+    /// Row and column of the area's top-left module
+    pub start: usize,
+    /// Width and height of the area, in modules
+    pub modules: usize,
+}
+
+impl LogoArea {
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        let range = self.start..self.start + self.modules;
+        range.contains(&x) && range.contains(&y)
+    }
+
+    /// Returns whether the area overlaps the `size` x `size` block of modules at (x, y)
+    pub fn overlaps(&self, x: usize, y: usize, size: usize) -> bool {
+        let end = self.start + self.modules;
+        x < end && self.start < x + size && y < end && self.start < y + size
+    }
+}
+
+/// Checks that a logo can be embedded, rejecting unsupported images, logos that reach the
+/// finder patterns and logos that hide more codewords than the error correction can recover
+pub(crate) fn validate_logo(matrix: &QrMatrix, logo: &Logo) -> Result<(), QrError> {
+    logo.image.data_uri()?;
+    check_error_budget(matrix, cleared_area(matrix.width(), logo)?)
+}
+
+/// Works out which modules a logo covers in a symbol `width` modules wide
+pub(crate) fn cleared_area(width: usize, logo: &Logo) -> Result<LogoArea, QrError> {
+    if !(logo.size > 0.0 && logo.size <= 1.0) {
+        return Err(QrError::InvalidConfig(format!("logo size {} is outside (0, 1]", logo.size)));
+    }
+    let mut modules = (logo.size * width as f32).ceil() as usize + 2 * logo.padding;
+    // Match the symbol's parity so the area sits exactly in the centre
+    if modules % 2 != width % 2 {
+        modules += 1;
+    }
+    // Finder patterns, separators and format information occupy the outer 9 modules
+    if modules + 2 * 9 > width {
+        return Err(QrError::InvalidConfig(format!(
+            "logo covers {modules}x{modules} modules and would hide the finder patterns of a {width}x{width} symbol"
+        )));
+    }
+    Ok(LogoArea { start: (width - modules) / 2, modules })
+}
+
+/// Checks that no Reed-Solomon block loses more codewords under the area than it can correct
+///
+/// Every codeword with at least one module in the area counts as an error, since a
+/// scanner may read the logo as any mix of dark and light modules.
+pub(crate) fn check_error_budget(matrix: &QrMatrix, area: LogoArea) -> Result<(), QrError> {
+    let blocks = rs_blocks(matrix.version(), matrix.error_correction());
+    let owners = codeword_blocks(&blocks);
+    let mut damaged = vec![0; blocks.len()];
+    let mut counted = vec![false; owners.len()];
+    for (index, (x, y)) in data_module_positions(matrix.version()).into_iter().enumerate() {
+        let codeword = index / 8;
+        // Remainder bits after the last codeword carry no data
+        if codeword < owners.len() && !counted[codeword] && area.contains(x, y) {
+            counted[codeword] = true;
+            damaged[owners[codeword]] += 1;
+        }
+    }
+    let correctable = correctable_errors_per_block(matrix.version(), matrix.error_correction());
+    let worst = damaged.into_iter().max().unwrap_or(0);
+    if worst > correctable {
+        return Err(QrError::LogoTooLarge { damaged_codewords: worst, correctable_codewords: correctable });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_qr_matrix, ErrorCorrectionLevel, QrEncodeOptions, VersionConstraint};
+
+    fn matrix(error_correction: ErrorCorrectionLevel) -> QrMatrix {
+        let options = QrEncodeOptions {
+            error_correction,
+            version: VersionConstraint::Fixed(5),
+            ..QrEncodeOptions::default()
+        };
+        generate_qr_matrix("https://jedi.org", &options).unwrap()
+    }
+
+    fn logo(size: f32) -> Logo {
+        Logo::new(LogoImage::Svg("<svg xmlns='http://www.w3.org/2000/svg'/>".to_string()), size)
+    }
+
+    #[test]
+    fn centres_cleared_area_on_symbol() {
+        // 37 module symbol: 20% is 7.4 modules, rounded up to 8, plus padding, made odd
+        let area = cleared_area(37, &logo(0.2)).unwrap();
+        assert_eq!(area, LogoArea { start: 13, modules: 11 });
+        assert!(area.contains(13, 23) && !area.contains(12, 18) && !area.contains(24, 18));
+        assert!(!area.overlaps(26, 26, 5) && area.overlaps(22, 22, 5));
+    }
+
+    #[test]
+    fn refuses_logos_beyond_error_correction_budget() {
+        assert!(validate_logo(&matrix(ErrorCorrectionLevel::High), &logo(0.2)).is_ok());
+        let result = validate_logo(&matrix(ErrorCorrectionLevel::Low), &logo(0.2));
+        assert!(matches!(result, Err(QrError::LogoTooLarge { correctable_codewords: 13, .. })));
+    }
+
+    #[test]
+    fn refuses_invalid_sizes() {
+        let matrix = matrix(ErrorCorrectionLevel::High);
+        for size in [0.0, -0.5, 1.5, f32::NAN] {
+            assert!(matches!(validate_logo(&matrix, &logo(size)), Err(QrError::InvalidConfig(_))));
+        }
+        // Large enough to reach the finder patterns
+        assert!(matches!(validate_logo(&matrix, &logo(0.6)), Err(QrError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn builds_data_uris() {
+        assert!(logo(0.2).data_uri().unwrap().starts_with("data:image/svg+xml;base64,PHN2Zy"));
+        let jpeg = Logo::new(LogoImage::Raster(vec![0xFF, 0xD8, 0xFF, 0xE0]), 0.2);
+        assert!(jpeg.data_uri().unwrap().starts_with("data:image/jpeg;base64,"));
+        let unknown = Logo::new(LogoImage::Raster(b"GIF89a".to_vec()), 0.2);
+        assert!(matches!(unknown.data_uri(), Err(QrError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn wraps_svg_fragments_in_a_root() {
+        let fragment = "<circle cx='50' cy='50' r='40' fill='#ff00ff'/><rect x='30' y='80' width='20' height='30'/>";
+        assert_eq!(
+            svg_document(fragment).unwrap(),
+            format!(
+                "<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink' viewBox='10 10 80 100'>{fragment}</svg>"
+            )
+        );
+        let document = "<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 10 10'/>";
+        assert_eq!(svg_document(document).unwrap(), document);
+        let declared = format!("<?xml version='1.0'?>\n{document}");
+        assert_eq!(svg_document(&declared).unwrap(), declared);
+
+        for broken in ["<circle r='4'>", "<g/>", "not svg"] {
+            let logo = Logo::new(LogoImage::Svg(broken.to_string()), 0.2);
+            assert!(matches!(logo.data_uri(), Err(QrError::InvalidConfig(_))), "{broken}");
+        }
+    }
+}
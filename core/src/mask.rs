@@ -0,0 +1,110 @@
+// ISO/IEC 18004 mask penalty scoring
+//
+// The qrcode crate picks the lowest-penalty mask internally but does not expose the scores,
+// so they are recomputed here for reporting and manual mask selection.
+
+/// Number of mask patterns defined for (non-micro) QR codes
+pub const MASK_PATTERN_COUNT: usize = 8;
+
+// Penalty weights from ISO/IEC 18004 section 7.8.3.1
+const N1: u32 = 3;
+const N2: u32 = 3;
+const N3: u32 = 40;
+const N4: u32 = 10;
+
+// Finder-like 1:1:3:1:1 pattern preceded or followed by 4 light modules
+const FINDER_LIKE: [bool; 11] = [true, false, true, true, true, false, true, false, false, false, false];
+const FINDER_LIKE_REVERSED: [bool; 11] = [false, false, false, false, true, false, true, true, true, false, true];
+
+/// Computes the total penalty score of a masked symbol
+///
+/// `modules` holds the symbol in row-major order, `true` meaning dark.
+pub fn penalty_score(modules: &[bool], width: usize) -> u32 {
+    let at = |x: usize, y: usize| modules[y * width + x];
+    let row = |y: usize| (0..width).map(move |x| at(x, y)).collect::<Vec<_>>();
+    let column = |x: usize| (0..width).map(move |y| at(x, y)).collect::<Vec<_>>();
+
+    let mut score = 0;
+    for i in 0..width {
+        for line in [row(i), column(i)] {
+            score += run_penalty(&line) + finder_like_penalty(&line);
+        }
+    }
+    score += block_penalty(modules, width);
+    score += balance_penalty(modules);
+    score
+}
+
+/// N1: runs of five or more same-coloured modules in a line
+fn run_penalty(line: &[bool]) -> u32 {
+    let mut score = 0;
+    let mut run = 1;
+    for i in 1..=line.len() {
+        if i < line.len() && line[i] == line[i - 1] {
+            run += 1;
+            continue;
+        }
+        if run >= 5 {
+            score += N1 + (run - 5);
+        }
+        run = 1;
+    }
+    score
+}
+
+/// N2: 2x2 blocks of the same colour
+fn block_penalty(modules: &[bool], width: usize) -> u32 {
+    let mut score = 0;
+    for y in 0..width - 1 {
+        for x in 0..width - 1 {
+            let color = modules[y * width + x];
+            if modules[y * width + x + 1] == color
+                && modules[(y + 1) * width + x] == color
+                && modules[(y + 1) * width + x + 1] == color
+            {
+                score += N2;
+            }
+        }
+    }
+    score
+}
+
+/// N3: finder-like patterns in a line
+fn finder_like_penalty(line: &[bool]) -> u32 {
+    let count = line
+        .windows(FINDER_LIKE.len())
+        .filter(|window| *window == FINDER_LIKE || *window == FINDER_LIKE_REVERSED)
+        .count();
+    count as u32 * N3
+}
+
+/// N4: deviation of the dark module proportion from 50%, in 5% steps
+fn balance_penalty(modules: &[bool]) -> u32 {
+    let total = modules.len();
+    let dark = modules.iter().filter(|&&dark| dark).count();
+    let deviation = (dark * 2).abs_diff(total) * 10 / total;
+    deviation as u32 * N4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_each_penalty_rule() {
+        // Runs of 5 score 3, each extra module adds 1
+        assert_eq!(run_penalty(&[true; 5]), 3);
+        assert_eq!(run_penalty(&[false, true, true, true, true, true, true, true]), 5);
+        assert_eq!(run_penalty(&[true, false, true, false]), 0);
+
+        // A 3x3 dark square contains four 2x2 blocks
+        assert_eq!(block_penalty(&[true; 9], 3), 12);
+
+        assert_eq!(finder_like_penalty(&FINDER_LIKE), 40);
+        assert_eq!(finder_like_penalty(&FINDER_LIKE_REVERSED), 40);
+
+        // Half dark is balanced, all dark deviates by 50% (10 steps of 5%)
+        assert_eq!(balance_penalty(&[true, false, true, false]), 0);
+        assert_eq!(balance_penalty(&[true; 4]), 100);
+    }
+}
@@ -0,0 +1,481 @@
+/// Which of the eight modules around a data module are also drawn as dark data modules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Neighbours {
+    /// Indexed by `[dy + 1][dx + 1]`
+    dark: [[bool; 3]; 3],
+}
+
+impl Neighbours {
+    /// Looks up the neighbours of (x, y) in a row-major `width` x `width` grid of drawn modules
+    pub(crate) fn of(drawn: &[bool], width: usize, x: usize, y: usize) -> Self {
+        let mut dark = [[false; 3]; 3];
+        for (dy, row) in dark.iter_mut().enumerate() {
+            for (dx, cell) in row.iter_mut().enumerate() {
+                let (nx, ny) = ((x + dx).checked_sub(1), (y + dy).checked_sub(1));
+                if let (Some(nx), Some(ny)) = (nx, ny) {
+                    *cell = nx < width && ny < width && (dx, dy) != (1, 1) && drawn[ny * width + nx];
+                }
+            }
+        }
+        Neighbours { dark }
+    }
+
+    /// Returns whether the module `dx` columns and `dy` rows away is drawn, both in -1..=1
+    pub fn is_dark(&self, dx: isize, dy: isize) -> bool {
+        self.dark[(dy + 1) as usize][(dx + 1) as usize]
+    }
+}
+
+/// Largest number of decimal places an `f32` coordinate can meaningfully be written with
+pub(crate) const MAX_PRECISION: u8 = 6;
+
+/// Rounds an SVG coordinate to `precision` decimal places
+pub(crate) fn round(value: f32, precision: u8) -> f32 {
+    let scale = 10f32.powi(i32::from(precision.min(MAX_PRECISION)));
+    // Adding zero turns a rounded -0 into 0, which is written without the sign
+    (value * scale).round() / scale + 0.0
+}
+
+/// A dark data module about to be drawn
+#[derive(Debug, Clone, Copy)]
+pub struct ModuleContext {
+    /// Column of the module within the symbol, excluding the quiet zone
+    pub x: usize,
+    /// Row of the module within the symbol, excluding the quiet zone
+    pub y: usize,
+    /// Pixel position of the module's left edge in the SVG
+    pub px: f32,
+    /// Pixel position of the module's top edge in the SVG
+    pub py: f32,
+    /// Width and height of the module, in pixels
+    pub size: f32,
+    /// Which of the surrounding modules are also drawn as dark data modules
+    pub neighbours: Neighbours,
+    /// Decimal places coordinates are written with
+    pub precision: u8,
+}
+
+impl ModuleContext {
+    /// Rounds a coordinate to the configured precision
+    pub fn round(&self, value: f32) -> f32 {
+        round(value, self.precision)
+    }
+}
+
+/// The size finder patterns are drawn at
+#[derive(Debug, Clone, Copy)]
+pub struct FinderContext {
+    /// Width and height of one module, in pixels
+    pub module_size: f32,
+    /// Decimal places coordinates are written with
+    pub precision: u8,
+}
+
+impl FinderContext {
+    /// Rounds a coordinate to the configured precision
+    pub fn round(&self, value: f32) -> f32 {
+        round(value, self.precision)
+    }
+
+    /// Returns a length of `modules` modules in pixels, rounded to the configured precision
+    fn modules(&self, modules: f32) -> f32 {
+        self.round(modules * self.module_size)
+    }
+}
+
+/// Draws dark data modules one at a time, for shapes that are not built into `DataShape`
+///
+/// Use an implementation through `DataShape::Custom`.
+pub trait ModuleShape: Send + Sync {
+    /// Returns the SVG elements drawing `module`, painted with `fill`
+    ///
+    /// `fill` is a colour or a `url(#...)` gradient reference for the elements' `fill`
+    /// attribute. Scanners sample the centre of each module, so the shape should cover it.
+    fn render(&self, module: &ModuleContext, fill: &str) -> String;
+}
+
+/// Draws the outer frame and inner eye of finder patterns, for shapes that are not built into `FinderShape`
+///
+/// Both parts are drawn for the top-left finder pattern, with the pattern's top-left corner
+/// at the origin, and mirrored into the other corners. Use an implementation through
+/// `FinderShape::Custom`.
+pub trait FinderPatternShape: Send + Sync {
+    /// Returns the SVG elements drawing the 7x7 frame, 1 module thick, painted with `fill`
+    fn render_frame(&self, finder: &FinderContext, fill: &str) -> String;
+    /// Returns the SVG elements drawing the 3x3 eye, 2 modules in from the origin, painted with `fill`
+    fn render_eye(&self, finder: &FinderContext, fill: &str) -> String;
+}
+
+pub(crate) struct SquareModule;
+
+impl ModuleShape for SquareModule {
+    fn render(&self, module: &ModuleContext, fill: &str) -> String {
+        format!(
+            "  <rect x='{x}' y='{y}' width='{w}' height='{w}' fill='{fill}'/>\n",
+            x = module.round(module.px),
+            y = module.round(module.py),
+            w = module.round(module.size),
+        )
+    }
+}
+
+pub(crate) struct RoundedModule;
+
+impl ModuleShape for RoundedModule {
+    fn render(&self, module: &ModuleContext, fill: &str) -> String {
+        format!(
+            "  <rect x='{x}' y='{y}' width='{w}' height='{w}' fill='{fill}' rx='{r}' ry='{r}'/>\n",
+            x = module.round(module.px),
+            y = module.round(module.py),
+            w = module.round(module.size),
+            r = module.round(module.size / 4.0),
+        )
+    }
+}
+
+pub(crate) struct DotModule;
+
+impl ModuleShape for DotModule {
+    fn render(&self, module: &ModuleContext, fill: &str) -> String {
+        let radius = module.size / 2.0;
+        format!(
+            "  <circle cx='{cx}' cy='{cy}' r='{r}' fill='{fill}'/>\n",
+            cx = module.round(module.px + radius),
+            cy = module.round(module.py + radius),
+            r = module.round(radius),
+        )
+    }
+}
+
+pub(crate) struct TriangleModule;
+
+impl ModuleShape for TriangleModule {
+    fn render(&self, module: &ModuleContext, fill: &str) -> String {
+        let (left, top) = (module.round(module.px), module.round(module.py));
+        let (right, bottom) = (module.round(module.px + module.size), module.round(module.py + module.size));
+        format!(
+            "  <polygon points='{x1},{y1} {x2},{y2} {x3},{y3}' fill='{fill}'/>\n",
+            x1 = module.round(module.px + module.size / 2.0),
+            y1 = top,
+            x2 = left,
+            y2 = bottom,
+            x3 = right,
+            y3 = bottom,
+        )
+    }
+}
+
+pub(crate) struct LiquidModule;
+
+impl ModuleShape for LiquidModule {
+    fn render(&self, module: &ModuleContext, fill: &str) -> String {
+        format!("  <path d='{d}' fill='{fill}'/>\n", d = liquid_module_path(module))
+    }
+}
+
+/// A lone module of the line shapes, drawn as a bar one module long
+pub(crate) struct LineModule {
+    pub thickness: f32,
+    pub horizontal: bool,
+}
+
+impl ModuleShape for LineModule {
+    fn render(&self, module: &ModuleContext, fill: &str) -> String {
+        render_bar(module, 1, self.thickness, self.horizontal, fill)
+    }
+}
+
+/// Builds the path data of a `Liquid` data module
+///
+/// Each corner is rounded when neither of the sides meeting there has a dark neighbour.
+/// Where the module is the corner of an L of three dark modules, a concave fillet is added
+/// in the light module that completes the 2x2 block, so the L flows around its inner corner.
+/// Every subpath runs clockwise so they combine into one path under the nonzero fill rule.
+pub(crate) fn liquid_module_path(module: &ModuleContext) -> String {
+    let neighbours = &module.neighbours;
+    let radius = module.round(module.size / 2.0);
+    let (left, top) = (module.round(module.px), module.round(module.py));
+    let (right, bottom) = (module.round(module.px + module.size), module.round(module.py + module.size));
+    let rounded = |dx, dy| !neighbours.is_dark(dx, 0) && !neighbours.is_dark(0, dy);
+    let inset = |dx, dy| if rounded(dx, dy) { radius } else { 0.0 };
+    let arc = |dx, dy, x: f32, y: f32| {
+        if rounded(dx, dy) {
+            format!("A{radius},{radius} 0 0 1 {},{}", module.round(x), module.round(y))
+        } else {
+            String::new()
+        }
+    };
+
+    let mut d = format!("M{},{top}", module.round(left + inset(-1, -1)));
+    d.push_str(&format!("H{}{}", module.round(right - inset(1, -1)), arc(1, -1, right, top + radius)));
+    d.push_str(&format!("V{}{}", module.round(bottom - inset(1, 1)), arc(1, 1, right - radius, bottom)));
+    d.push_str(&format!("H{}{}", module.round(left + inset(-1, 1)), arc(-1, 1, left, bottom - radius)));
+    d.push_str(&format!("V{}{}Z", module.round(top + inset(-1, -1)), arc(-1, -1, left + radius, top)));
+
+    for (dx, dy) in [(-1, -1), (1, -1), (1, 1), (-1, 1)] {
+        if neighbours.is_dark(dx, 0) && neighbours.is_dark(0, dy) && !neighbours.is_dark(dx, dy) {
+            // The fillet fills the corner of the diagonal module up to a quarter circle
+            let corner_x = if dx < 0 { left } else { right };
+            let corner_y = if dy < 0 { top } else { bottom };
+            let along_x = (module.round(corner_x + dx as f32 * radius), corner_y);
+            let along_y = (corner_x, module.round(corner_y + dy as f32 * radius));
+            // Visit the two ends in the order that keeps the fillet clockwise
+            let (first, second) = if dx * dy > 0 { (along_x, along_y) } else { (along_y, along_x) };
+            d.push_str(&format!(
+                "M{corner_x},{corner_y}L{},{}A{radius},{radius} 0 0 0 {},{}Z",
+                first.0, first.1, second.0, second.1
+            ));
+        }
+    }
+    d
+}
+
+/// Renders a bar with fully rounded ends covering `length` modules from `module`
+pub(crate) fn render_bar(module: &ModuleContext, length: usize, thickness: f32, horizontal: bool, color: &str) -> String {
+    // Non-finite thicknesses fall back to full modules rather than producing invalid SVG
+    let thickness = if thickness.is_finite() { thickness.clamp(0.0, 1.0) } else { 1.0 };
+    let bar = thickness * module.size;
+    let inset = (module.size - bar) / 2.0;
+    let long = length as f32 * module.size;
+    let (x, y, width, height) = if horizontal {
+        (module.px, module.py + inset, long, bar)
+    } else {
+        (module.px + inset, module.py, bar, long)
+    };
+    format!(
+        "  <rect x='{x}' y='{y}' width='{width}' height='{height}' rx='{r}' ry='{r}' fill='{color}'/>\n",
+        x = module.round(x),
+        y = module.round(y),
+        width = module.round(width),
+        height = module.round(height),
+        r = module.round(bar / 2.0),
+    )
+}
+
+pub(crate) struct SquareFinder;
+
+impl FinderPatternShape for SquareFinder {
+    fn render_frame(&self, finder: &FinderContext, color: &str) -> String {
+        format!(
+            "                        <rect width='{outer_bar_width}' height='{outer_bar_thickness}' fill='{color}'/>
+                        <rect y='{bottom_bar_y}' width='{outer_bar_width}' height='{outer_bar_thickness}' fill='{color}'/>
+                        <rect width='{outer_bar_thickness}' height='{outer_bar_height}' fill='{color}' x='{left_bar_x}' y='{left_bar_y}'/>
+                        <rect width='{outer_bar_thickness}' height='{outer_bar_height}' fill='{color}' x='{right_bar_x}' y='{right_bar_y}'/>
+",
+            color = color,
+            outer_bar_thickness = finder.modules(1.0), // 1 module thickness
+            outer_bar_width = finder.modules(7.0),     // 7 modules wide
+            outer_bar_height = finder.modules(5.0),    // 5 modules tall (7 total - 1 top - 1 bottom)
+            bottom_bar_y = finder.modules(6.0),        // Starts at module row 6
+            left_bar_x = 0,                            // Starts at module col 0
+            left_bar_y = finder.modules(1.0),          // Starts at module row 1
+            right_bar_x = finder.modules(6.0),         // Starts at module col 6
+            right_bar_y = finder.modules(1.0),         // Starts at module row 1
+        )
+    }
+
+    fn render_eye(&self, finder: &FinderContext, color: &str) -> String {
+        format!(
+            "                        <rect x='{inner_pos}' y='{inner_pos}' width='{inner_size}' height='{inner_size}' fill='{color}'/>\n",
+            color = color,
+            inner_size = finder.modules(3.0), // 3x3 modules for the innermost black square
+            inner_pos = finder.modules(2.0)   // Offset by 2 modules
+        )
+    }
+}
+
+pub(crate) struct TriangleFinder;
+
+impl FinderPatternShape for TriangleFinder {
+    fn render_frame(&self, finder: &FinderContext, color: &str) -> String {
+        // A 1 module thick ring with the corner facing into the symbol cut off diagonally, so
+        // it slopes like the eye's hypotenuse; the hole is cut out by filling even-odd
+        let cut = 6.0 - std::f32::consts::SQRT_2;
+        format!(
+            "                        <path d='M0,0 H{outer} V{outer_cut} L{outer_cut},{outer} H0 Z M{inner},{inner} H{far} V{cut} L{cut},{far} H{inner} Z' fill='{color}' fill-rule='evenodd'/>\n",
+            outer = finder.modules(7.0),
+            outer_cut = finder.modules(5.0),
+            inner = finder.modules(1.0),
+            far = finder.modules(6.0),
+            cut = finder.modules(cut),
+            color = color,
+        )
+    }
+
+    fn render_eye(&self, finder: &FinderContext, color: &str) -> String {
+        // A right triangle in the 3x3 eye, its right angle in the outer corner
+        format!(
+            "                        <path d='M{near},{near} H{far} L{near},{far} Z' fill='{color}'/>\n",
+            near = finder.modules(2.0),
+            far = finder.modules(5.0),
+            color = color,
+        )
+    }
+}
+
+pub(crate) struct DotFinder;
+
+impl FinderPatternShape for DotFinder {
+    fn render_frame(&self, finder: &FinderContext, color: &str) -> String {
+        let center = finder.modules(3.5);
+        let ring_radius = finder.modules(3.0);
+        let ring_stroke_width = finder.modules(1.0);
+
+        format!(
+            "                        <circle cx='{center}' cy='{center}' r='{ring_radius}' fill='none' stroke='{color}' stroke-width='{ring_stroke_width}'/>\n",
+            center = center,
+            ring_radius = ring_radius,
+            ring_stroke_width = ring_stroke_width,
+            color = color,
+        )
+    }
+
+    fn render_eye(&self, finder: &FinderContext, color: &str) -> String {
+        let center = finder.modules(3.5);
+        let r_dot = finder.modules(1.5);
+
+        format!(
+            "                        <circle cx='{center}' cy='{center}' r='{r_dot}' fill='{color}'/>\n",
+            center = center,
+            r_dot = r_dot,
+            color = color,
+        )
+    }
+}
+
+pub(crate) struct RoundedFinder;
+
+impl FinderPatternShape for RoundedFinder {
+    fn render_frame(&self, finder: &FinderContext, color: &str) -> String {
+        // A square with rounded off edges, halfway between the square and dot frames
+        let frame_offset = finder.modules(0.5);
+        let frame_size = finder.modules(6.0);
+        let frame_stroke_width = finder.modules(1.0);
+        let frame_corner_radius = finder.modules(2.0);
+
+        format!(
+            "                        <rect x='{frame_offset}' y='{frame_offset}' width='{frame_size}' height='{frame_size}' rx='{frame_corner_radius}' ry='{frame_corner_radius}' fill='none' stroke='{color}' stroke-width='{frame_stroke_width}'/>\n",
+            color = color,
+            frame_offset = frame_offset,
+            frame_size = frame_size,
+            frame_stroke_width = frame_stroke_width,
+            frame_corner_radius = frame_corner_radius,
+        )
+    }
+
+    fn render_eye(&self, finder: &FinderContext, color: &str) -> String {
+        let dot_offset = finder.modules(2.0);
+        let dot_size = finder.modules(3.0);
+        let dot_corner_radius = finder.modules(1.0);
+
+        format!(
+            "                        <rect x='{dot_offset}' y='{dot_offset}' width='{dot_size}' height='{dot_size}' rx='{dot_corner_radius}' ry='{dot_corner_radius}' fill='{color}'/>\n",
+            color = color,
+            dot_offset = dot_offset,
+            dot_size = dot_size,
+            dot_corner_radius = dot_corner_radius,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(neighbours: Neighbours) -> ModuleContext {
+        ModuleContext { x: 2, y: 3, px: 60.0, py: 70.0, size: 10.0, neighbours, precision: 2 }
+    }
+
+    fn at_origin(neighbours: Neighbours) -> ModuleContext {
+        ModuleContext { px: 0.0, py: 0.0, ..module(neighbours) }
+    }
+
+    #[test]
+    fn looks_up_neighbours_within_the_symbol() {
+        // Row 0: ##. / row 1: .#. / row 2: ..#
+        let drawn = [true, true, false, false, true, false, false, false, true];
+        let neighbours = Neighbours::of(&drawn, 3, 1, 1);
+        assert!(neighbours.is_dark(-1, -1) && neighbours.is_dark(0, -1) && neighbours.is_dark(1, 1));
+        assert!(!neighbours.is_dark(0, 0) && !neighbours.is_dark(1, -1) && !neighbours.is_dark(-1, 0));
+        // Modules beyond the edge of the symbol are light
+        assert_eq!(Neighbours::of(&drawn, 3, 0, 0).dark[0], [false; 3]);
+    }
+
+    #[test]
+    fn renders_built_in_module_shapes() {
+        let module = module(Neighbours::default());
+        assert_eq!(SquareModule.render(&module, "red"), "  <rect x='60' y='70' width='10' height='10' fill='red'/>\n");
+        assert_eq!(DotModule.render(&module, "red"), "  <circle cx='65' cy='75' r='5' fill='red'/>\n");
+        assert!(RoundedModule.render(&module, "red").contains("rx='2.5' ry='2.5'"));
+        assert!(TriangleModule.render(&module, "red").contains("points='65,70 60,80 70,80'"));
+        assert!(LiquidModule.render(&module, "url(#g)").contains("fill='url(#g)'"));
+    }
+
+    #[test]
+    fn keeps_fractional_geometry_to_the_requested_precision() {
+        // Odd module sizes keep dots centred rather than truncating the radius
+        let odd = ModuleContext { size: 7.0, ..module(Neighbours::default()) };
+        assert_eq!(DotModule.render(&odd, "red"), "  <circle cx='63.5' cy='73.5' r='3.5' fill='red'/>\n");
+        assert!(RoundedModule.render(&odd, "red").contains("rx='1.75' ry='1.75'"));
+
+        let thirds = ModuleContext { px: 10.0 / 3.0, size: 10.0 / 3.0, precision: 1, ..module(Neighbours::default()) };
+        assert_eq!(SquareModule.render(&thirds, "red"), "  <rect x='3.3' y='70' width='3.3' height='3.3' fill='red'/>\n");
+        let whole = ModuleContext { precision: 0, ..thirds };
+        assert!(DotModule.render(&whole, "red").contains("cx='5' cy='72' r='2'"));
+
+        let finder = FinderContext { module_size: 2.5, precision: 2 };
+        assert!(DotFinder.render_eye(&finder, "red").contains("cx='8.75' cy='8.75' r='3.75'"));
+        assert!(SquareFinder.render_eye(&finder, "red").contains("x='5' y='5' width='7.5' height='7.5'"));
+    }
+
+    #[test]
+    fn draws_triangle_finders_as_a_closed_ring() {
+        let finder = FinderContext { module_size: 10.0, precision: 2 };
+        assert_eq!(
+            TriangleFinder.render_frame(&finder, "red").trim(),
+            "<path d='M0,0 H70 V50 L50,70 H0 Z M10,10 H60 V45.86 L45.86,60 H10 Z' fill='red' fill-rule='evenodd'/>"
+        );
+        assert_eq!(TriangleFinder.render_eye(&finder, "red").trim(), "<path d='M20,20 H50 L20,50 Z' fill='red'/>");
+
+        // The cut keeps the ring a module thick across the diagonal: x + y = 120 outside, 120 - 10√2 inside
+        let thickness = (120.0 - (60.0 + 45.86)) / std::f32::consts::SQRT_2;
+        assert!((thickness - 10.0).abs() < 0.01, "{thickness}");
+    }
+
+    #[test]
+    fn rounds_liquid_modules_by_their_neighbours() {
+        // A lone module is rounded on every corner, becoming a circle
+        let alone = Neighbours { dark: [[false; 3]; 3] };
+        assert_eq!(liquid_module_path(&at_origin(alone)), "M5,0H5A5,5 0 0 1 10,5V5A5,5 0 0 1 5,10H5A5,5 0 0 1 0,5V5A5,5 0 0 1 5,0Z");
+
+        // With a dark module to the right, only the left corners are rounded
+        let mut right = alone;
+        right.dark[1][2] = true;
+        assert_eq!(liquid_module_path(&at_origin(right)), "M5,0H10V10H5A5,5 0 0 1 0,5V5A5,5 0 0 1 5,0Z");
+
+        // The corner of an L gets a fillet in the light module below and to the right
+        let mut corner = right;
+        corner.dark[2][1] = true;
+        let d = liquid_module_path(&at_origin(corner));
+        assert!(d.starts_with("M5,0H10V10H0V5A5,5 0 0 1 5,0Z"), "{d}");
+        assert!(d.ends_with("M10,10L15,10A5,5 0 0 0 10,15Z"), "{d}");
+
+        // Filling in the diagonal removes the fillet
+        corner.dark[2][2] = true;
+        assert_eq!(liquid_module_path(&at_origin(corner)).matches('M').count(), 1);
+    }
+
+    #[test]
+    fn renders_bars() {
+        assert_eq!(
+            render_bar(&ModuleContext { px: 40.0, py: 50.0, ..module(Neighbours::default()) }, 3, 0.6, true, "red"),
+            "  <rect x='40' y='52' width='30' height='6' rx='3' ry='3' fill='red'/>\n"
+        );
+        assert_eq!(
+            render_bar(&ModuleContext { px: 40.0, py: 50.0, ..module(Neighbours::default()) }, 2, 1.5, false, "red"),
+            "  <rect x='40' y='50' width='10' height='20' rx='5' ry='5' fill='red'/>\n"
+        );
+        let line = LineModule { thickness: f32::NAN, horizontal: true };
+        assert!(line.render(&module(Neighbours::default()), "red").contains("width='10' height='10' rx='5'"));
+    }
+}
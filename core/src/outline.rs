@@ -0,0 +1,137 @@
+use crate::module_shape::round;
+
+/// Traces the outlines of connected groups of modules into SVG path data
+///
+/// `modules` lists the (x, y) positions of the filled modules in a `width` x `width`
+/// grid. Each connected region becomes one closed subpath around its outer edge,
+/// plus one per hole. Outer edges run clockwise and holes anticlockwise, so the
+/// path fills correctly under the default nonzero fill rule and, unlike one shape
+/// per module, leaves no antialiasing seams between neighbouring modules.
+/// Coordinates are scaled by `module_size`, shifted by `offset` pixels and rounded to `precision` decimal places.
+pub(crate) fn trace_outline(modules: &[(usize, usize)], width: usize, module_size: f32, offset: f32, precision: u8) -> String {
+    let mut filled = vec![false; width * width];
+    for &(x, y) in modules {
+        filled[y * width + x] = true;
+    }
+    let is_filled = |x: isize, y: isize| {
+        x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < width && filled[y as usize * width + x as usize]
+    };
+
+    // Collect the clockwise boundary edges of every filled module that face an empty neighbour.
+    // Edges between two filled modules are never created, which is what merges the modules.
+    let vertices = width + 1;
+    let vertex = |x: usize, y: usize| y * vertices + x;
+    let mut edges: Vec<(usize, usize)> = Vec::new();
+    for &(x, y) in modules {
+        let (ix, iy) = (x as isize, y as isize);
+        if !is_filled(ix, iy - 1) {
+            edges.push((vertex(x, y), vertex(x + 1, y)));
+        }
+        if !is_filled(ix + 1, iy) {
+            edges.push((vertex(x + 1, y), vertex(x + 1, y + 1)));
+        }
+        if !is_filled(ix, iy + 1) {
+            edges.push((vertex(x + 1, y + 1), vertex(x, y + 1)));
+        }
+        if !is_filled(ix - 1, iy) {
+            edges.push((vertex(x, y + 1), vertex(x, y)));
+        }
+    }
+    let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); vertices * vertices];
+    for (index, &(from, _)) in edges.iter().enumerate() {
+        outgoing[from].push(index);
+    }
+
+    let point = |vertex: usize| (vertex % vertices, vertex / vertices);
+    let direction = |(from, to): (usize, usize)| {
+        let ((x0, y0), (x1, y1)) = (point(from), point(to));
+        (x1 as isize - x0 as isize, y1 as isize - y0 as isize)
+    };
+
+    let mut used = vec![false; edges.len()];
+    let mut path = String::new();
+    for start in 0..edges.len() {
+        if used[start] {
+            continue;
+        }
+        // Walk the loop, keeping only the corners where the direction changes
+        let mut corners = Vec::new();
+        let mut current = start;
+        loop {
+            used[current] = true;
+            let (_, to) = edges[current];
+            let heading = direction(edges[current]);
+            // Where two regions touch diagonally, turn right to stay on the current region
+            let next = outgoing[to]
+                .iter()
+                .copied()
+                .filter(|&edge| !used[edge] || edge == start)
+                .max_by_key(|&edge| {
+                    let (dx, dy) = direction(edges[edge]);
+                    heading.0 * dy - heading.1 * dx
+                });
+            let Some(next) = next else { break };
+            if direction(edges[next]) != heading {
+                corners.push(to);
+            }
+            if next == start {
+                break;
+            }
+            current = next;
+        }
+
+        let scale = |coordinate: usize| round(offset + coordinate as f32 * module_size, precision);
+        for (index, &corner) in corners.iter().enumerate() {
+            let (x, y) = point(corner);
+            if index == 0 {
+                path.push_str(&format!("M{},{}", scale(x), scale(y)));
+            } else if point(corners[index - 1]).0 == x {
+                path.push_str(&format!("V{}", scale(y)));
+            } else {
+                path.push_str(&format!("H{}", scale(x)));
+            }
+        }
+        path.push('Z');
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traces_single_module() {
+        assert_eq!(trace_outline(&[(1, 1)], 3, 10.0, 0.0, 2), "M20,10V20H10V10Z");
+    }
+
+    #[test]
+    fn merges_neighbouring_modules() {
+        // An L shape becomes one outline with six corners
+        let path = trace_outline(&[(0, 0), (1, 0), (0, 1)], 2, 10.0, 5.0, 2);
+        assert_eq!(path.matches('M').count(), 1);
+        assert_eq!(path, "M25,5V15H15V25H5V5Z");
+    }
+
+    #[test]
+    fn traces_holes_in_the_opposite_direction() {
+        let ring: Vec<(usize, usize)> =
+            (0..3).flat_map(|y| (0..3).map(move |x| (x, y))).filter(|&position| position != (1, 1)).collect();
+        let path = trace_outline(&ring, 3, 1.0, 0.0, 2);
+        assert_eq!(path.matches('M').count(), 2);
+        assert!(path.contains("M3,0V3H0V0Z"), "{path}");
+        assert!(path.contains("M1,1V2H2V1Z"), "{path}");
+    }
+
+    #[test]
+    fn keeps_diagonal_neighbours_separate() {
+        let path = trace_outline(&[(0, 0), (1, 1)], 2, 1.0, 0.0, 2);
+        assert_eq!(path.matches('M').count(), 2);
+        assert!(path.contains("M1,0V1H0V0Z") && path.contains("M2,1V2H1V1Z"), "{path}");
+    }
+
+    #[test]
+    fn rounds_fractional_module_sizes() {
+        assert_eq!(trace_outline(&[(1, 1)], 3, 2.0 / 3.0, 0.5, 3), "M1.833,1.167V1.833H1.167V1.167Z");
+    }
+}
@@ -0,0 +1,75 @@
+/// A projective transform of the plane, mapping a quadrilateral onto any other
+///
+/// Used to sample a symbol's modules from a photographed or warped image, and to warp
+/// images for scannability testing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Perspective {
+    /// Row-major 3x3 matrix with its last entry fixed to 1
+    coefficients: [f64; 8],
+}
+
+impl Perspective {
+    /// Returns the transform mapping each of the four `from` points onto the matching `to` point,
+    /// or `None` when three of the points are collinear
+    pub(crate) fn between(from: [(f64, f64); 4], to: [(f64, f64); 4]) -> Option<Self> {
+        // Each correspondence gives two linear equations in the eight unknown coefficients
+        let mut system = [[0.0; 9]; 8];
+        for (i, (&(u, v), &(x, y))) in from.iter().zip(&to).enumerate() {
+            system[2 * i] = [u, v, 1.0, 0.0, 0.0, 0.0, -u * x, -v * x, x];
+            system[2 * i + 1] = [0.0, 0.0, 0.0, u, v, 1.0, -u * y, -v * y, y];
+        }
+
+        // Gaussian elimination with partial pivoting
+        for column in 0..8 {
+            let pivot = (column..8)
+                .max_by(|&a, &b| system[a][column].abs().total_cmp(&system[b][column].abs()))
+                .unwrap_or(column);
+            if system[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            system.swap(column, pivot);
+            let pivot_row = system[column];
+            for (_, row) in system.iter_mut().enumerate().filter(|&(index, _)| index != column) {
+                let factor = row[column] / pivot_row[column];
+                for (value, pivot) in row.iter_mut().zip(pivot_row).skip(column) {
+                    *value -= factor * pivot;
+                }
+            }
+        }
+        let mut coefficients = [0.0; 8];
+        for (i, coefficient) in coefficients.iter_mut().enumerate() {
+            *coefficient = system[i][8] / system[i][i];
+        }
+        Some(Perspective { coefficients })
+    }
+
+    /// Maps a point through the transform
+    pub(crate) fn map(&self, (u, v): (f64, f64)) -> (f64, f64) {
+        let [a, b, c, d, e, f, g, h] = self.coefficients;
+        let w = g * u + h * v + 1.0;
+        ((a * u + b * v + c) / w, (d * u + e * v + f) / w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_corners_onto_corners() {
+        let square = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        let keystone = [(10.0, 5.0), (90.0, 20.0), (100.0, 80.0), (0.0, 100.0)];
+        let transform = Perspective::between(square, keystone).unwrap();
+        for (corner, expected) in square.into_iter().zip(keystone) {
+            let (x, y) = transform.map(corner);
+            assert!((x - expected.0).abs() < 1e-9 && (y - expected.1).abs() < 1e-9, "{corner:?}");
+        }
+        // Straight lines stay straight: the centre lies on both diagonals
+        let (x, y) = transform.map((0.5, 0.5));
+        let on_diagonal = |(x0, y0): (f64, f64), (x1, y1): (f64, f64)| ((x1 - x0) * (y - y0) - (y1 - y0) * (x - x0)).abs() < 1e-6;
+        assert!(on_diagonal(keystone[0], keystone[2]) && on_diagonal(keystone[1], keystone[3]));
+
+        let collinear = [(0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (0.0, 1.0)];
+        assert_eq!(Perspective::between(collinear, keystone), None);
+    }
+}
@@ -0,0 +1,363 @@
+use crate::encoding::{EncodedQr, ErrorCorrectionLevel};
+
+/// The structural role a module plays in the QR code symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModuleRole {
+    /// One of the three 7x7 position detection patterns
+    Finder,
+    /// The light border separating a finder pattern from the rest of the symbol
+    Separator,
+    /// One of the 5x5 alignment patterns (version 2 and up)
+    Alignment,
+    /// The alternating timing lines along row and column 6
+    Timing,
+    /// Format information, including the always-dark module next to the bottom-left finder
+    Format,
+    /// Version information blocks (version 7 and up)
+    Version,
+    /// Data codewords, including the remainder bits that pad the data region
+    Data,
+    /// Reed-Solomon error correction codewords
+    ErrorCorrection,
+}
+
+/// A single module of the symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Module {
+    /// Whether the module is dark
+    pub dark: bool,
+    /// The role of the module in the symbol
+    pub role: ModuleRole,
+}
+
+/// An encoded QR code symbol with the metadata needed to style it
+#[derive(Debug, Clone, PartialEq)]
+pub struct QrMatrix {
+    version: u8,
+    error_correction: ErrorCorrectionLevel,
+    mask: u8,
+    width: usize,
+    modules: Vec<Module>,
+}
+
+impl QrMatrix {
+    pub(crate) fn from_encoded(code: &EncodedQr) -> Self {
+        let roles = module_roles(code.version, code.data_codewords);
+        let modules = code
+            .modules
+            .iter()
+            .zip(roles)
+            .map(|(&dark, role)| Module { dark, role })
+            .collect();
+        QrMatrix {
+            version: code.version,
+            error_correction: code.error_correction,
+            mask: code.mask,
+            width: code.width,
+            modules,
+        }
+    }
+
+    /// QR code version (1-40) of the symbol
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Error correction level the symbol was encoded with
+    pub fn error_correction(&self) -> ErrorCorrectionLevel {
+        self.error_correction
+    }
+
+    /// Mask pattern (0-7) applied to the symbol
+    pub fn mask(&self) -> u8 {
+        self.mask
+    }
+
+    /// Number of modules per side, excluding any quiet zone
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the module at column `x` and row `y`
+    pub fn get(&self, x: usize, y: usize) -> Module {
+        self.modules[y * self.width + x]
+    }
+
+    /// Whether the module at column `x` and row `y` is dark
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.get(x, y).dark
+    }
+
+    /// The role of the module at column `x` and row `y`
+    pub fn role(&self, x: usize, y: usize) -> ModuleRole {
+        self.get(x, y).role
+    }
+
+    /// Iterates over every module as `(x, y, module)` in row-major order
+    pub fn modules(&self) -> impl Iterator<Item = (usize, usize, Module)> + '_ {
+        self.modules
+            .iter()
+            .enumerate()
+            .map(move |(i, &module)| (i % self.width, i / self.width, module))
+    }
+
+    /// Converts the symbol into the legacy grid of booleans, where true = dark module
+    pub fn to_bool_matrix(&self) -> Vec<Vec<bool>> {
+        self.modules
+            .chunks(self.width)
+            .map(|row| row.iter().map(|module| module.dark).collect())
+            .collect()
+    }
+}
+
+impl From<&QrMatrix> for Vec<Vec<bool>> {
+    fn from(matrix: &QrMatrix) -> Self {
+        matrix.to_bool_matrix()
+    }
+}
+
+// Alignment pattern centre coordinates for versions 2-40 (ISO/IEC 18004 Annex E)
+static ALIGNMENT_PATTERN_POSITIONS: [&[usize]; 39] = [
+    &[6, 18],
+    &[6, 22],
+    &[6, 26],
+    &[6, 30],
+    &[6, 34],
+    &[6, 22, 38],
+    &[6, 24, 42],
+    &[6, 26, 46],
+    &[6, 28, 50],
+    &[6, 30, 54],
+    &[6, 32, 58],
+    &[6, 34, 62],
+    &[6, 26, 46, 66],
+    &[6, 26, 48, 70],
+    &[6, 26, 50, 74],
+    &[6, 30, 54, 78],
+    &[6, 30, 56, 82],
+    &[6, 30, 58, 86],
+    &[6, 34, 62, 90],
+    &[6, 28, 50, 72, 94],
+    &[6, 26, 50, 74, 98],
+    &[6, 30, 54, 78, 102],
+    &[6, 28, 54, 80, 106],
+    &[6, 32, 58, 84, 110],
+    &[6, 30, 58, 86, 114],
+    &[6, 34, 62, 90, 118],
+    &[6, 26, 50, 74, 98, 122],
+    &[6, 30, 54, 78, 102, 126],
+    &[6, 26, 52, 78, 104, 130],
+    &[6, 30, 56, 82, 108, 134],
+    &[6, 34, 60, 86, 112, 138],
+    &[6, 30, 58, 86, 114, 142],
+    &[6, 34, 62, 90, 118, 146],
+    &[6, 30, 54, 78, 102, 126, 150],
+    &[6, 24, 50, 76, 102, 128, 154],
+    &[6, 28, 54, 80, 106, 132, 158],
+    &[6, 32, 58, 84, 110, 136, 162],
+    &[6, 26, 54, 82, 110, 138, 166],
+    &[6, 30, 58, 86, 114, 142, 170],
+];
+
+/// Returns the centres of every alignment pattern in a symbol of the given version
+pub(crate) fn alignment_pattern_centres(version: u8) -> Vec<(usize, usize)> {
+    if version < 2 {
+        return Vec::new();
+    }
+    let positions = ALIGNMENT_PATTERN_POSITIONS[version as usize - 2];
+    let last = *positions.last().unwrap_or(&0);
+    let mut centres = Vec::new();
+    for &y in positions {
+        for &x in positions {
+            // Skip the three corners occupied by finder patterns
+            let overlaps_finder = (x == 6 && (y == 6 || y == last)) || (x == last && y == 6);
+            if !overlaps_finder {
+                centres.push((x, y));
+            }
+        }
+    }
+    centres
+}
+
+/// Returns the role of a function-pattern module, or `None` for the encoding region
+fn function_role(version: u8, width: usize, x: usize, y: usize) -> Option<ModuleRole> {
+    let near = |a: usize, limit: usize| a < limit;
+    let far = |a: usize, limit: usize| a >= width - limit;
+
+    // Finder patterns and their separators
+    for (in_x, in_y) in [(near(x, 8), near(y, 8)), (far(x, 8), near(y, 8)), (near(x, 8), far(y, 8))] {
+        if in_x && in_y {
+            let fx = if x < 8 { x } else { width - 1 - x };
+            let fy = if y < 8 { y } else { width - 1 - y };
+            return Some(if fx < 7 && fy < 7 { ModuleRole::Finder } else { ModuleRole::Separator });
+        }
+    }
+
+    // Format information beside the finders, plus the dark module
+    if (y == 8 && (x <= 8 || far(x, 8))) || (x == 8 && (y <= 8 || far(y, 8))) {
+        return Some(if x == 6 || y == 6 { ModuleRole::Timing } else { ModuleRole::Format });
+    }
+
+    // Version information blocks
+    if version >= 7 && ((x < 6 && far(y, 11) && !far(y, 8)) || (y < 6 && far(x, 11) && !far(x, 8))) {
+        return Some(ModuleRole::Version);
+    }
+
+    if alignment_pattern_centres(version)
+        .iter()
+        .any(|&(cx, cy)| x.abs_diff(cx) <= 2 && y.abs_diff(cy) <= 2)
+    {
+        return Some(ModuleRole::Alignment);
+    }
+
+    if x == 6 || y == 6 {
+        return Some(ModuleRole::Timing);
+    }
+
+    None
+}
+
+/// Returns the coordinates of the encoding region in codeword placement order
+///
+/// Bits are placed in two-module wide columns, starting at the bottom right and
+/// zigzagging upwards and downwards, skipping the vertical timing line.
+pub(crate) fn data_module_positions(version: u8) -> Vec<(usize, usize)> {
+    let width = 17 + 4 * version as usize;
+    let mut positions = Vec::new();
+    let mut upwards = true;
+    let mut right = width as isize - 1;
+    while right > 0 {
+        if right == 6 {
+            // The vertical timing line is not part of any column pair
+            right -= 1;
+        }
+        for step in 0..width {
+            let y = if upwards { width - 1 - step } else { step };
+            for x in [right as usize, right as usize - 1] {
+                if function_role(version, width, x, y).is_none() {
+                    positions.push((x, y));
+                }
+            }
+        }
+        upwards = !upwards;
+        right -= 2;
+    }
+    positions
+}
+
+/// Whether mask pattern `mask` (0-7) inverts the module at column `x` and row `y`
+#[cfg(any(test, feature = "verify"))]
+pub(crate) fn mask_applies(mask: u8, x: usize, y: usize) -> bool {
+    match mask {
+        0 => (x + y).is_multiple_of(2),
+        1 => y.is_multiple_of(2),
+        2 => x.is_multiple_of(3),
+        3 => (x + y).is_multiple_of(3),
+        4 => (y / 2 + x / 3).is_multiple_of(2),
+        5 => (x * y) % 2 + (x * y) % 3 == 0,
+        6 => ((x * y) % 2 + (x * y) % 3).is_multiple_of(2),
+        _ => ((x + y) % 2 + (x * y) % 3).is_multiple_of(2),
+    }
+}
+
+/// Classifies every module of a symbol, in row-major order
+fn module_roles(version: u8, data_codewords: usize) -> Vec<ModuleRole> {
+    let width = 17 + 4 * version as usize;
+    let mut roles: Vec<Option<ModuleRole>> = (0..width * width)
+        .map(|i| function_role(version, width, i % width, i / width))
+        .collect();
+    let positions = data_module_positions(version);
+    let total_codewords = positions.len() / 8;
+    for (bit, (x, y)) in positions.into_iter().enumerate() {
+        let codeword = bit / 8;
+        let is_ec = codeword >= data_codewords && codeword < total_codewords;
+        roles[y * width + x] = Some(if is_ec { ModuleRole::ErrorCorrection } else { ModuleRole::Data });
+    }
+    roles.into_iter().map(|role| role.unwrap_or(ModuleRole::Data)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::{encode_qr_code, MaskSelection, QrEncodeOptions, VersionConstraint};
+    use qrcode::bits::Bits;
+    use qrcode::{EcLevel, Version};
+
+    fn encode(data: &str, version: u8) -> EncodedQr {
+        // A fixed mask keeps the expectations independent of penalty scoring
+        let options = QrEncodeOptions {
+            version: VersionConstraint::Fixed(version),
+            mask: MaskSelection::Fixed(2),
+            ..QrEncodeOptions::default()
+        };
+        encode_qr_code(data, &options).unwrap()
+    }
+
+    fn expected_codewords(data: &str, version: u8) -> Vec<u8> {
+        let mut bits = Bits::new(Version::Normal(version as i16));
+        bits.push_optimal_data(data.as_bytes()).unwrap();
+        bits.push_terminator(EcLevel::M).unwrap();
+        let (data, ec) =
+            qrcode::ec::construct_codewords(&bits.into_bytes(), Version::Normal(version as i16), EcLevel::M).unwrap();
+        [data, ec].concat()
+    }
+
+    #[test]
+    fn classifies_function_patterns() {
+        let matrix = QrMatrix::from_encoded(&encode("https://jedi.org", 7));
+        let width = matrix.width();
+        assert_eq!(width, 45);
+        assert_eq!(matrix.version(), 7);
+        assert_eq!(matrix.mask(), 2);
+
+        assert_eq!(matrix.role(0, 0), ModuleRole::Finder);
+        assert_eq!(matrix.role(6, 6), ModuleRole::Finder);
+        assert_eq!(matrix.role(7, 3), ModuleRole::Separator);
+        assert_eq!(matrix.role(width - 8, 7), ModuleRole::Separator);
+        assert_eq!(matrix.role(10, 6), ModuleRole::Timing);
+        assert_eq!(matrix.role(6, 10), ModuleRole::Timing);
+        assert_eq!(matrix.role(8, 2), ModuleRole::Format);
+        assert_eq!(matrix.role(8, width - 8), ModuleRole::Format);
+        assert!(matrix.is_dark(8, width - 8));
+        assert_eq!(matrix.role(0, width - 11), ModuleRole::Version);
+        assert_eq!(matrix.role(width - 9, 5), ModuleRole::Version);
+        // Version 7 has alignment patterns centred on 6, 22 and 38
+        assert_eq!(matrix.role(22, 22), ModuleRole::Alignment);
+        assert_eq!(matrix.role(38, 36), ModuleRole::Alignment);
+        assert_eq!(matrix.role(6, 22), ModuleRole::Alignment);
+        assert!(matrix.is_dark(22, 22));
+
+        let count = |role| matrix.modules().filter(|(_, _, m)| m.role == role).count();
+        assert_eq!(count(ModuleRole::Finder), 3 * 49);
+        assert_eq!(count(ModuleRole::Alignment), 6 * 25);
+        assert_eq!(count(ModuleRole::Version), 2 * 18);
+        // Version 7 holds 196 codewords: 124 data and 72 error correction at level M
+        assert_eq!(count(ModuleRole::ErrorCorrection), 72 * 8);
+        assert_eq!(count(ModuleRole::Data), 124 * 8);
+    }
+
+    #[test]
+    fn reads_back_codewords_in_placement_order() {
+        for version in [1, 2, 7, 14] {
+            let code = encode("jedi", version);
+            let bits: Vec<bool> = data_module_positions(version)
+                .into_iter()
+                .map(|(x, y)| code.modules[y * code.width + x] ^ mask_applies(code.mask, x, y))
+                .collect();
+            let codewords: Vec<u8> = bits
+                .chunks_exact(8)
+                .map(|byte| byte.iter().fold(0, |acc, &bit| (acc << 1) | bit as u8))
+                .collect();
+            assert_eq!(codewords, expected_codewords("jedi", version));
+        }
+    }
+
+    #[test]
+    fn converts_to_legacy_bool_grid() {
+        let matrix = QrMatrix::from_encoded(&encode("https://jedi.org", 2));
+        let grid: Vec<Vec<bool>> = (&matrix).into();
+        assert_eq!(grid.len(), matrix.width());
+        assert!(grid[0][0]);
+        assert_eq!(grid[5][9], matrix.is_dark(9, 5));
+    }
+}
@@ -0,0 +1,201 @@
+// Reed-Solomon error correction over GF(256), as QR codes use it
+//
+// Codewords are coefficients of a polynomial, highest degree first, and the generator
+// polynomial has the consecutive roots α^0, α^1, ... so a valid block evaluates to zero
+// at every one of them.
+
+use crate::error::QrError;
+
+/// Exponents and logarithms of GF(256) with the primitive polynomial x^8 + x^4 + x^3 + x^2 + 1
+struct Tables {
+    /// α^i, repeated so sums of two logarithms need no reduction
+    exp: [u8; 510],
+    log: [u8; 256],
+}
+
+const fn tables() -> Tables {
+    let mut exp = [0; 510];
+    let mut log = [0; 256];
+    let mut value: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = value as u8;
+        exp[i + 255] = value as u8;
+        log[value as usize] = i as u8;
+        value <<= 1;
+        if value & 0x100 != 0 {
+            value ^= 0x11d;
+        }
+        i += 1;
+    }
+    Tables { exp, log }
+}
+
+static TABLES: Tables = tables();
+
+fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    TABLES.exp[TABLES.log[a as usize] as usize + TABLES.log[b as usize] as usize]
+}
+
+fn div(a: u8, b: u8) -> u8 {
+    if a == 0 {
+        return 0;
+    }
+    TABLES.exp[TABLES.log[a as usize] as usize + 255 - TABLES.log[b as usize] as usize]
+}
+
+/// α raised to `power`, which may be any non-negative integer
+fn alpha(power: usize) -> u8 {
+    TABLES.exp[power % 255]
+}
+
+/// Evaluates a polynomial stored lowest degree first
+fn evaluate(polynomial: &[u8], x: u8) -> u8 {
+    polynomial.iter().rev().fold(0, |acc, &coefficient| mul(acc, x) ^ coefficient)
+}
+
+/// Evaluates the received block, highest degree first, at α^0 to α^(ec - 1)
+fn syndromes(block: &[u8], ec_codewords: usize) -> Vec<u8> {
+    (0..ec_codewords)
+        .map(|i| block.iter().fold(0, |acc, &codeword| mul(acc, alpha(i)) ^ codeword))
+        .collect()
+}
+
+/// Corrects a block of data codewords followed by its `ec_codewords` error correction codewords
+/// in place, returning how many codewords were wrong
+///
+/// Fails with `QrError::Verification` when the block has more than `max_errors` errors, or so
+/// many that they cannot be located.
+pub(crate) fn correct(block: &mut [u8], ec_codewords: usize, max_errors: usize) -> Result<usize, QrError> {
+    let uncorrectable = || QrError::Verification("too many codeword errors to correct".to_string());
+    let syndromes = syndromes(block, ec_codewords);
+    if syndromes.iter().all(|&syndrome| syndrome == 0) {
+        return Ok(0);
+    }
+
+    // Berlekamp-Massey finds the shortest error locator polynomial generating the syndromes
+    let mut locator = vec![1u8];
+    let mut previous = vec![1u8];
+    let mut errors = 0;
+    let mut shift = 1;
+    let mut previous_discrepancy = 1u8;
+    for n in 0..ec_codewords {
+        let discrepancy = (1..=errors.min(locator.len() - 1))
+            .fold(syndromes[n], |acc, i| acc ^ mul(locator[i], syndromes[n - i]));
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+        let scale = div(discrepancy, previous_discrepancy);
+        let mut updated = locator.clone();
+        updated.resize(updated.len().max(previous.len() + shift), 0);
+        for (i, &coefficient) in previous.iter().enumerate() {
+            updated[i + shift] ^= mul(scale, coefficient);
+        }
+        if 2 * errors <= n {
+            previous = std::mem::replace(&mut locator, updated);
+            errors = n + 1 - errors;
+            previous_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            locator = updated;
+            shift += 1;
+        }
+    }
+    if errors > max_errors {
+        return Err(uncorrectable());
+    }
+
+    // The locator's roots are the inverses of α^degree for every erroneous codeword
+    let length = block.len();
+    let positions: Vec<usize> = (0..length)
+        .filter(|&position| evaluate(&locator, alpha(255 - (length - 1 - position) % 255)) == 0)
+        .collect();
+    if positions.len() != errors {
+        return Err(uncorrectable());
+    }
+
+    // Forney's algorithm gives each error's value from the evaluator and the locator's derivative
+    let mut evaluator = vec![0u8; ec_codewords];
+    for (i, &syndrome) in syndromes.iter().enumerate() {
+        for (j, &coefficient) in locator.iter().enumerate().take(ec_codewords - i) {
+            evaluator[i + j] ^= mul(syndrome, coefficient);
+        }
+    }
+    let derivative: Vec<u8> = locator
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, &coefficient)| if i % 2 == 1 { coefficient } else { 0 })
+        .collect();
+    for position in positions {
+        let location = alpha(length - 1 - position);
+        let inverse = div(1, location);
+        let denominator = evaluate(&derivative, inverse);
+        if denominator == 0 {
+            return Err(uncorrectable());
+        }
+        block[position] ^= mul(location, div(evaluate(&evaluator, inverse), denominator));
+    }
+
+    if syndromes_are_zero(block, ec_codewords) {
+        Ok(errors)
+    } else {
+        Err(uncorrectable())
+    }
+}
+
+fn syndromes_are_zero(block: &[u8], ec_codewords: usize) -> bool {
+    syndromes(block, ec_codewords).iter().all(|&syndrome| syndrome == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(data: &[u8], ec_codewords: usize) -> Vec<u8> {
+        [data.to_vec(), qrcode::ec::create_error_correction_code(data, ec_codewords)].concat()
+    }
+
+    #[test]
+    fn builds_the_field() {
+        assert_eq!(alpha(8), 0x1d);
+        assert_eq!(mul(alpha(100), alpha(200)), alpha(300));
+        assert_eq!(div(mul(0x53, 0xca), 0xca), 0x53);
+    }
+
+    #[test]
+    fn corrects_up_to_half_the_error_correction_codewords() {
+        let data: Vec<u8> = (0..26u8).map(|i| i.wrapping_mul(37)).collect();
+        let original = block(&data, 16);
+        for errors in 0..=8 {
+            let mut received = original.clone();
+            for i in 0..errors {
+                received[i * 5] ^= 0x5a + i as u8;
+            }
+            assert_eq!(correct(&mut received, 16, 8), Ok(errors), "{errors} errors");
+            assert_eq!(received, original);
+        }
+    }
+
+    #[test]
+    fn refuses_blocks_beyond_the_budget() {
+        let original = block(b"https://jedi.org", 10);
+        let mut received = original.clone();
+        for i in [0, 3, 7] {
+            received[i] ^= 0xff;
+        }
+        // Three errors are within what 10 codewords correct, but not within a budget of two
+        assert!(correct(&mut received.clone(), 10, 2).is_err());
+        assert_eq!(correct(&mut received, 10, 5), Ok(3));
+
+        let mut garbled = original;
+        for codeword in garbled.iter_mut().take(12) {
+            *codeword = !*codeword;
+        }
+        assert!(correct(&mut garbled, 10, 5).is_err());
+    }
+}
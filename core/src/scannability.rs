@@ -0,0 +1,253 @@
+use std::fmt;
+use image::{imageops, GrayImage, Luma};
+use crate::error::QrError;
+use crate::format_conversion::QrCodeResult;
+use crate::perspective::Perspective;
+use crate::verification::{check_decoded, decode_greyscale};
+
+/// A kind of damage a code suffers between the renderer and a scanner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Degradation {
+    /// Gaussian blur from an out of focus or shaken camera, severity in module widths
+    Blur,
+    /// Sensor noise, severity in grey levels the pixels may stray by either way
+    Noise,
+    /// Perspective distortion from a camera below and to the side of the code, severity the
+    /// fraction the top and right edges shrink by
+    Skew,
+    /// Capture at a low resolution, severity in pixels per module
+    Downscale,
+    /// A smudge, sticker or glare spot, severity the fraction of the image covered
+    Occlusion,
+}
+
+impl Degradation {
+    pub const ALL: [Degradation; 5] = [
+        Degradation::Blur,
+        Degradation::Noise,
+        Degradation::Skew,
+        Degradation::Downscale,
+        Degradation::Occlusion,
+    ];
+
+    /// Severities tried, from mild to harsh
+    pub fn severities(self) -> [f32; 4] {
+        match self {
+            Degradation::Blur => [0.2, 0.35, 0.5, 0.7],
+            Degradation::Noise => [16.0, 32.0, 48.0, 64.0],
+            Degradation::Skew => [0.05, 0.1, 0.15, 0.2],
+            Degradation::Downscale => [3.0, 2.0, 1.5, 1.0],
+            Degradation::Occlusion => [0.01, 0.02, 0.04, 0.06],
+        }
+    }
+
+    /// Applies the degradation to a dark on light image with `module_pixels` pixels per module
+    fn apply(self, image: &GrayImage, module_pixels: f32, severity: f32) -> GrayImage {
+        match self {
+            Degradation::Blur => imageops::blur(image, severity * module_pixels),
+            Degradation::Noise => add_noise(image, severity),
+            Degradation::Skew => tilt(image, severity),
+            Degradation::Downscale => {
+                let scale = severity / module_pixels;
+                if scale >= 1.0 {
+                    return image.clone();
+                }
+                let width = ((image.width() as f32 * scale).round() as u32).max(1);
+                let height = ((image.height() as f32 * scale).round() as u32).max(1);
+                imageops::resize(image, width, height, imageops::FilterType::Triangle)
+            }
+            Degradation::Occlusion => occlude(image, severity),
+        }
+    }
+}
+
+impl fmt::Display for Degradation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Degradation::Blur => "blur",
+            Degradation::Noise => "noise",
+            Degradation::Skew => "skew",
+            Degradation::Downscale => "downscale",
+            Degradation::Occlusion => "occlusion",
+        })
+    }
+}
+
+/// Whether the code survived one degradation at one severity
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenarioResult {
+    pub degradation: Degradation,
+    /// Strength of the degradation, in the units `Degradation` documents
+    pub severity: f32,
+    pub decoded: bool,
+}
+
+/// How well a rendered code survives real-world conditions
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScannabilityReport {
+    /// Share of scenarios that decoded, from 0 to 100, comparable between styles of the same data
+    pub score: u8,
+    /// Whether the undamaged code decoded. When it does not, no scenario is tried and the score is 0.
+    pub baseline: bool,
+    /// Every degradation at every severity, in the order of `Degradation::ALL` and `severities`
+    pub scenarios: Vec<ScenarioResult>,
+}
+
+/// Adds uniform noise of up to `amplitude` grey levels either way, the same for every run
+fn add_noise(image: &GrayImage, amplitude: f32) -> GrayImage {
+    // xorshift32, so the score of a code never changes between runs
+    let mut state: u32 = 0x9e37_79b9;
+    let mut noisy = image.clone();
+    for pixel in noisy.pixels_mut() {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        let offset = (state as f32 / u32::MAX as f32 * 2.0 - 1.0) * amplitude;
+        pixel.0[0] = (f32::from(pixel.0[0]) + offset).round().clamp(0.0, 255.0) as u8;
+    }
+    noisy
+}
+
+/// Warps the image as if seen from below and to the side, shrinking the top and right edges by
+/// `amount` of their length about their middles while the bottom left corner stays put
+fn tilt(image: &GrayImage, amount: f32) -> GrayImage {
+    let (width, height) = image.dimensions();
+    let (w, h, amount) = (f64::from(width), f64::from(height), f64::from(amount));
+    let corners = [(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)];
+    let tilted = [(amount * w / 2.0, 0.0), (w - amount * w / 2.0, amount * h / 2.0), (w, h - amount * h / 2.0), (0.0, h)];
+    let Some(transform) = Perspective::between(tilted, corners) else {
+        return image.clone();
+    };
+    GrayImage::from_fn(width, height, |x, y| {
+        let (source_x, source_y) = transform.map((f64::from(x) + 0.5, f64::from(y) + 0.5));
+        if source_x < 0.0 || source_y < 0.0 || source_x > w || source_y > h {
+            return Luma([255]);
+        }
+        let (source_x, source_y) = ((source_x - 0.5).clamp(0.0, w - 1.0), (source_y - 0.5).clamp(0.0, h - 1.0));
+        // Bilinear interpolation between the four nearest source pixels
+        let (left, top) = (source_x.floor() as u32, source_y.floor() as u32);
+        let (right, bottom) = ((left + 1).min(width - 1), (top + 1).min(height - 1));
+        let (across, down) = (source_x - f64::from(left), source_y - f64::from(top));
+        let value = |x, y| f64::from(image.get_pixel(x, y).0[0]);
+        let upper = value(left, top) * (1.0 - across) + value(right, top) * across;
+        let lower = value(left, bottom) * (1.0 - across) + value(right, bottom) * across;
+        Luma([(upper * (1.0 - down) + lower * down).round() as u8])
+    })
+}
+
+/// Covers `fraction` of the image with a mid grey square in the middle, where it damages data
+/// rather than the finder patterns, so the code is still found and error correction is tested
+fn occlude(image: &GrayImage, fraction: f32) -> GrayImage {
+    let (width, height) = image.dimensions();
+    let side = ((width * height) as f32 * fraction).sqrt();
+    let (center_x, center_y) = (width as f32 / 2.0, height as f32 / 2.0);
+    let left = (center_x - side / 2.0).round().max(0.0) as u32;
+    let top = (center_y - side / 2.0).round().max(0.0) as u32;
+    let right = ((center_x + side / 2.0).round() as u32).min(width);
+    let bottom = ((center_y + side / 2.0).round() as u32).min(height);
+    let mut occluded = image.clone();
+    for y in top..bottom {
+        for x in left..right {
+            occluded.put_pixel(x, y, Luma([128]));
+        }
+    }
+    occluded
+}
+
+impl QrCodeResult {
+    /// Estimates how robust the rendered code is by damaging it the way cameras and print do
+    ///
+    /// The output is rasterized like `verify` does, then blurred, made noisy, skewed, downscaled
+    /// and partially covered, each at increasing severities, and every result decoded and
+    /// checked against `expected`. The score is the share of these scenarios that decoded, so
+    /// styles of the same data can be ranked against each other.
+    pub fn scannability(&self, expected: &str) -> Result<ScannabilityReport, QrError> {
+        let (image, module_pixels) = self.greyscale()?;
+        let baseline = check_decoded(decode_greyscale(&image), expected).is_ok();
+
+        let mut scenarios = Vec::new();
+        for degradation in Degradation::ALL {
+            for severity in degradation.severities() {
+                let decoded = baseline && {
+                    let damaged = degradation.apply(&image, module_pixels, severity);
+                    check_decoded(decode_greyscale(&damaged), expected).is_ok()
+                };
+                scenarios.push(ScenarioResult { degradation, severity, decoded });
+            }
+        }
+        let passed = scenarios.iter().filter(|scenario| scenario.decoded).count();
+        let score = (100.0 * passed as f32 / scenarios.len() as f32).round() as u8;
+        Ok(ScannabilityReport { score, baseline, scenarios })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{render_qr_code, render_qr_code_svg, DataShape, DataStyle, FinderShape, QrRenderConfig, RasterFormat};
+
+    #[test]
+    fn scores_a_plain_code_highly() {
+        let data = "https://jedi.org/scan";
+        let report = render_qr_code(data, None, RasterFormat::Png, 400).unwrap().scannability(data).unwrap();
+        assert!(report.baseline);
+        assert_eq!(report.scenarios.len(), 20);
+        assert!(report.score >= 60, "{report:?}");
+        // The mildest of every degradation leaves a clean code readable
+        for degradation in Degradation::ALL {
+            let mildest = report.scenarios.iter().find(|scenario| scenario.degradation == degradation).unwrap();
+            assert!(mildest.decoded, "{mildest:?}");
+        }
+    }
+
+    #[test]
+    fn tilts_both_ways() {
+        let image = GrayImage::from_pixel(100, 100, Luma([0]));
+        let tilted = tilt(&image, 0.2);
+        let dark = |x, y| tilted.get_pixel(x, y).0[0] < 128;
+        // The corners move to (10, 0), (90, 10), (100, 90) and (0, 100), so the top edge
+        // slopes down to the right and the right edge slopes in towards the top
+        assert!(dark(50, 50) && dark(0, 99));
+        assert!(!dark(0, 0) && !dark(8, 0) && dark(12, 1));
+        assert!(!dark(88, 8) && dark(88, 12));
+        assert!(!dark(97, 50) && dark(93, 50));
+        assert!(!dark(99, 95) && dark(97, 88));
+    }
+
+    #[test]
+    fn scores_styled_finder_patterns() {
+        let data = "https://jedi.org/scan";
+        for (frame, eye) in [(FinderShape::Dot, FinderShape::Dot), (FinderShape::Triangle, FinderShape::Triangle)] {
+            let config = QrRenderConfig {
+                finder_frame_shape: frame,
+                finder_eye_shape: eye,
+                data_shape: DataShape::Dot,
+                ..QrRenderConfig::default()
+            };
+            let report = render_qr_code_svg(data, Some(&config)).unwrap().scannability(data).unwrap();
+            assert!(report.baseline);
+            assert!(report.score >= 50, "{report:?}");
+        }
+    }
+
+    #[test]
+    fn ranks_low_contrast_styles_lower() {
+        let data = "https://jedi.org/scan";
+        let plain = render_qr_code_svg(data, None).unwrap().scannability(data).unwrap();
+        let config = QrRenderConfig {
+            data_styling: DataStyle::color("#999").unwrap(),
+            ..QrRenderConfig::default()
+        };
+        let faint = render_qr_code_svg(data, Some(&config)).unwrap().scannability(data).unwrap();
+        assert!(faint.score < plain.score, "{} >= {}", faint.score, plain.score);
+    }
+
+    #[test]
+    fn scores_unreadable_codes_zero() {
+        let result = render_qr_code("https://jedi.org", None, RasterFormat::Png, 300).unwrap();
+        let report = result.scannability("https://sith.org").unwrap();
+        assert!(!report.baseline);
+        assert_eq!(report.score, 0);
+        assert!(report.scenarios.iter().all(|scenario| !scenario.decoded));
+    }
+}
@@ -1,30 +1,225 @@
-use log::info;
+use log::{info, warn};
+use crate::color::{escape_xml, Color};
+use crate::encoding::QrEncodeOptions;
+use crate::error::QrError;
+use crate::gradient::Gradient;
+use crate::logo::{check_error_budget, cleared_area, Logo, LogoArea, LogoImage};
+use crate::module_shape::{
+    liquid_module_path, render_bar, round, DotFinder, FinderContext, DotModule, FinderPatternShape, LineModule, LiquidModule, ModuleContext,
+    ModuleShape, Neighbours, RoundedFinder, RoundedModule, SquareFinder, SquareModule, TriangleFinder, TriangleModule,
+};
+use crate::outline::trace_outline;
+use crate::unit_path::UnitPath;
+use crate::qr_matrix::{alignment_pattern_centres, ModuleRole, QrMatrix};
 
 /// configuration for QR code rendering
 pub struct QrRenderConfig {
-    pub finder_shape: FinderShape,
+    /// Options used when encoding the data into a matrix
+    pub encoding: QrEncodeOptions,
+    /// Shape of the 1 module thick outer frame of each finder pattern
+    pub finder_frame_shape: FinderShape,
+    /// Shape of the 3x3 inner eye of each finder pattern
+    pub finder_eye_shape: FinderShape,
     pub data_shape: DataShape,
-    pub finder_styling: FinderStyle,
-    pub data_styling: DataStyle
+    /// How dark data modules are written to the SVG
+    pub data_render_mode: DataRenderMode,
+    pub finder_frame_styling: FinderStyle,
+    pub finder_eye_styling: FinderStyle,
+    pub data_styling: DataStyle,
+    /// Shape of the 5x5 alignment patterns present from version 2 upwards
+    pub alignment_shape: AlignmentShape,
+    pub alignment_styling: AlignmentStyle,
+    /// Width of the light margin around the symbol, in modules
+    /// The QR specification requires at least 4 modules for reliable scanning
+    pub quiet_zone: usize,
+    /// Logo drawn in the centre of the symbol, over modules cleared for it
+    pub logo: Option<Logo>,
+    /// Width and height of each module in SVG user units (pixels); may be fractional
+    pub module_size: f32,
+    /// Decimal places SVG coordinates are written with, at most 6
+    pub precision: u8,
+    /// Lay raster output out so every module covers a whole number of pixels, giving sharp
+    /// module edges; the quiet zone widens slightly to absorb the pixels left over
+    pub snap_to_pixels: bool,
+    /// Size the SVG declares for itself; the viewBox always spans the whole symbol
+    pub svg_size: SvgSize,
+    /// What is painted behind the symbol, quiet zone included
+    pub background: Background,
+    /// Opaque color JPEG output is flattened onto wherever the background is transparent,
+    /// since JPEG has no alpha channel. PNG output keeps the transparency instead.
+    pub matte: Color,
+    /// Draw an inverted code, with light modules on a dark ground. The data style then paints
+    /// the quiet zone and light modules, and the background shows through every dark module,
+    /// finder and alignment patterns included; the finder and alignment styles are unused.
+    pub inverted: bool,
+    /// Decode the rendered output before returning it, failing with `QrError::Verification`
+    /// when it does not read back as the input data. Needs the `verify` feature.
+    pub verify: bool,
+    /// Prefix of every id the SVG defines, its gradients and mask, so several codes can be
+    /// inlined into one page without their definitions clashing. Letters, digits, `-` and `_`.
+    pub id_prefix: String,
+}
+
+/// Quiet zone width required by the QR specification, in modules
+pub const DEFAULT_QUIET_ZONE: usize = 4;
+
+/// Width and height of each module unless configured otherwise, in pixels
+pub const DEFAULT_MODULE_SIZE: f32 = 10.0;
+
+/// Decimal places SVG coordinates are written with unless configured otherwise
+pub const DEFAULT_PRECISION: u8 = 2;
+
+/// Width and height attributes written on the SVG root element
+///
+/// The size covers the whole SVG, quiet zone included. The drawing is always
+/// described in pixels through the viewBox, so every option scales it uniformly.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SvgSize {
+    /// The size of the viewBox, one user unit per pixel
+    #[default]
+    Pixels,
+    /// No width or height, so the SVG scales to fit wherever it is embedded
+    ViewBoxOnly,
+    /// A physical size for print layouts
+    Millimetres(f32),
+    /// A physical size for print layouts
+    Inches(f32),
+    /// A percentage of the containing block's width
+    Percent(f32),
+}
+
+impl SvgSize {
+    /// Fails for sizes that are not positive and finite
+    pub(crate) fn validate(&self) -> Result<(), QrError> {
+        match *self {
+            SvgSize::Millimetres(size) | SvgSize::Inches(size) | SvgSize::Percent(size) if !(size.is_finite() && size > 0.0) => {
+                Err(QrError::InvalidConfig(format!("SVG size {size} must be positive")))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the physical width and height in millimetres, if the size is a physical one
+    pub(crate) fn millimetres(&self) -> Option<f32> {
+        match *self {
+            SvgSize::Millimetres(size) => Some(size),
+            SvgSize::Inches(size) => Some(size * 25.4),
+            _ => None,
+        }
+    }
+
+    /// Returns the width and height attributes for an SVG whose viewBox is `pixels` wide
+    fn attributes(&self, pixels: f32) -> String {
+        match self {
+            SvgSize::Pixels => format!(" width='{pixels}' height='{pixels}'"),
+            SvgSize::ViewBoxOnly => String::new(),
+            SvgSize::Millimetres(size) => format!(" width='{size}mm' height='{size}mm'"),
+            SvgSize::Inches(size) => format!(" width='{size}in' height='{size}in'"),
+            // A percentage height resolves against the container's height, so leave the
+            // height to follow from the width and the square viewBox
+            SvgSize::Percent(size) => format!(" width='{size}%'"),
+        }
+    }
+}
+
+/// What is painted behind the symbol
+pub enum Background {
+    /// Nothing, leaving the quiet zone and light modules transparent
+    None,
+    /// Solid background color
+    Color(Color),
+    /// Gradient spanning the whole SVG, quiet zone included
+    Gradient(Gradient),
+    /// Image covering the whole SVG, cropped to keep its aspect ratio
+    Image(LogoImage),
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Color(Color::WHITE)
+    }
+}
+
+impl Background {
+    /// Creates a solid background from a CSS color, failing for anything that is not a valid color
+    pub fn color(color: &str) -> Result<Self, QrError> {
+        Ok(Background::Color(color.parse()?))
+    }
 }
 
 pub enum DataStyle {
-    /// Color specification for the finder pattern
-    /// Can be any valid SVG color (named color, hex code, or rgb value)
-    Color(String)
+    /// Solid color shared by all data modules
+    Color(Color),
+    /// Gradient spanning the whole symbol, shared by all data modules
+    Gradient(Gradient),
 }
 
 pub enum FinderStyle {
-    /// Color specification for the finder pattern
-    /// Can be any valid SVG color (named color, hex code, or rgb value)
-    Color(String)
+    /// Solid color for the finder pattern
+    Color(Color),
+    /// Gradient spanning the whole symbol, so all three finder patterns sample the same gradient
+    Gradient(Gradient),
+}
+
+pub enum AlignmentStyle {
+    /// Solid color for the alignment patterns
+    Color(Color)
+}
+
+impl DataStyle {
+    /// Creates a solid style for the data modules from a CSS color, parsed like `Color::parse`
+    pub fn color(color: &str) -> Result<Self, QrError> {
+        Ok(DataStyle::Color(color.parse()?))
+    }
+}
+
+impl FinderStyle {
+    /// Creates a solid style for the finder patterns from a CSS color, parsed like `Color::parse`
+    pub fn color(color: &str) -> Result<Self, QrError> {
+        Ok(FinderStyle::Color(color.parse()?))
+    }
+}
+
+impl AlignmentStyle {
+    /// Creates a solid style for the alignment patterns from a CSS color, parsed like `Color::parse`
+    pub fn color(color: &str) -> Result<Self, QrError> {
+        Ok(AlignmentStyle::Color(color.parse()?))
+    }
 }
 
 pub enum FinderShape {
     Square,
     Dot,
     Rounded,
+    /// A ring with the corner facing into the symbol cut off diagonally, around a right
+    /// triangle eye with its right angle in the outer corner
     Triangle,
+    /// Path data in a unit box, stretched over the 7x7 frame or the 3x3 eye
+    CustomPath(UnitPath),
+    /// A shape drawn by a user supplied implementation
+    Custom(Box<dyn FinderPatternShape>),
+}
+
+impl FinderShape {
+    /// Returns the implementation drawing this shape
+    fn pattern_shape(&self) -> &dyn FinderPatternShape {
+        match self {
+            FinderShape::Square => &SquareFinder,
+            FinderShape::Dot => &DotFinder,
+            FinderShape::Rounded => &RoundedFinder,
+            FinderShape::Triangle => &TriangleFinder,
+            FinderShape::CustomPath(path) => path,
+            FinderShape::Custom(shape) => shape.as_ref(),
+        }
+    }
+}
+
+/// Shape used to draw each 5x5 alignment pattern as a single unit
+pub enum AlignmentShape {
+    Square,
+    Rounded,
+    /// A ring with a centre dot
+    Dot,
 }
 
 pub enum DataShape {
@@ -32,57 +227,219 @@ pub enum DataShape {
     Dot,
     Rounded,
     Triangle,
+    /// Rounded only on sides without a dark neighbour, with concave fillets in inner
+    /// corners, so neighbouring modules flow together into connected blobs
+    Liquid,
+    /// Runs of dark modules in a row merged into rounded horizontal bars
+    /// `thickness` is the bar height as a fraction of the module size, clamped to 0-1
+    HorizontalLines { thickness: f32 },
+    /// Runs of dark modules in a column merged into rounded vertical bars
+    /// `thickness` is the bar width as a fraction of the module size, clamped to 0-1
+    VerticalLines { thickness: f32 },
+    /// Path data in a unit box, scaled into the cell of each module
+    CustomPath(UnitPath),
+    /// A shape drawn one module at a time by a user supplied implementation
+    Custom(Box<dyn ModuleShape>),
+}
+
+/// A solid colour or gradient fill, borrowed from a style
+enum Fill<'a> {
+    Color(Color),
+    Gradient(&'a Gradient),
+}
+
+impl DataStyle {
+    fn fill(&self) -> Fill<'_> {
+        match self {
+            DataStyle::Color(color) => Fill::Color(*color),
+            DataStyle::Gradient(gradient) => Fill::Gradient(gradient),
+        }
+    }
+}
+
+impl FinderStyle {
+    fn fill(&self) -> Fill<'_> {
+        match self {
+            FinderStyle::Color(color) => Fill::Color(*color),
+            FinderStyle::Gradient(gradient) => Fill::Gradient(gradient),
+        }
+    }
+}
+
+impl QrRenderConfig {
+    /// Returns every gradient the config fills with
+    pub(crate) fn gradients(&self) -> Vec<&Gradient> {
+        let fills = [self.data_styling.fill(), self.finder_frame_styling.fill(), self.finder_eye_styling.fill()];
+        let mut gradients: Vec<&Gradient> = fills
+            .into_iter()
+            .filter_map(|fill| match fill {
+                Fill::Gradient(gradient) => Some(gradient),
+                Fill::Color(_) => None,
+            })
+            .collect();
+        if let Background::Gradient(gradient) = &self.background {
+            gradients.push(gradient);
+        }
+        gradients
+    }
+}
+
+/// Gradient definitions referenced by the symbol, emitted as SVG `<defs>`
+struct GradientDefs {
+    svg: String,
+    /// Pixel position of the symbol's top-left corner, inside the quiet zone
+    origin: f32,
+    /// Pixel size of the symbol, excluding the quiet zone
+    size: f32,
+    /// Set while an inverted code's modules are drawn into its mask, where every fill is black
+    masking: bool,
+    /// Prepended to every id, escaped for attributes
+    prefix: String,
+    /// Decimal places gradient coordinates are written with
+    precision: u8,
+}
+
+impl GradientDefs {
+    /// Returns the SVG paint for a fill, defining any gradient under the prefixed `id`
+    ///
+    /// `transform` maps symbol coordinates into the user space of the element being filled.
+    fn paint(&mut self, fill: Fill, id: &str, transform: Option<&str>) -> String {
+        if self.masking {
+            return Color::BLACK.to_string();
+        }
+        match fill {
+            Fill::Color(color) => escape_xml(&color.to_string()).into_owned(),
+            Fill::Gradient(gradient) => self.define(gradient, id, self.origin, self.size, transform),
+        }
+    }
+
+    /// Defines a gradient spanning `size` pixels from `origin` under the prefixed `id` and
+    /// returns the paint referencing it
+    ///
+    /// This function cannot fail, so a gradient `render_qr_code` refuses is only logged and
+    /// replaced by its first stop's color.
+    fn define(&mut self, gradient: &Gradient, id: &str, origin: f32, size: f32, transform: Option<&str>) -> String {
+        let id = self.id(id);
+        match gradient.to_svg_definition(&id, origin, size, transform, self.precision) {
+            Ok(definition) => {
+                self.svg.push_str(&definition);
+                format!("url(#{id})")
+            }
+            Err(error) => {
+                warn!("Replacing gradient with a solid color: {error}");
+                let (Gradient::Linear { stops, .. } | Gradient::Radial { stops, .. }) = gradient;
+                let color = stops.first().map_or(Color::BLACK, |stop| stop.color);
+                escape_xml(&color.to_string()).into_owned()
+            }
+        }
+    }
+
+    /// Returns `name` with the configured id prefix
+    fn id(&self, name: &str) -> String {
+        format!("{}-{name}", self.prefix)
+    }
+}
+
+/// How dark data modules are written to the SVG
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataRenderMode {
+    /// Combine the dark modules into a single path, giving a much smaller SVG with no
+    /// seams between neighbouring modules. Only `DataShape::Square`, `DataShape::Liquid` and
+    /// `DataShape::CustomPath` can be merged; other shapes are still drawn one element per module.
+    #[default]
+    MergedPath,
+    /// Draw one element per dark module
+    PerModule,
+}
+
+/// The corner of the symbol a finder pattern sits in
+#[derive(Clone, Copy)]
+enum FinderCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+}
+
+impl FinderCorner {
+    /// Suffix distinguishing the gradient definitions of each finder pattern
+    fn id(self) -> &'static str {
+        match self {
+            FinderCorner::TopLeft => "top-left",
+            FinderCorner::TopRight => "top-right",
+            FinderCorner::BottomLeft => "bottom-left",
+        }
+    }
 }
 
-/// Renders a finder pattern module as SVG
+/// Renders a finder pattern module as SVG, composed of the outer frame and the inner eye
+///
+/// Shapes are drawn for the top-left corner and mirrored into the other corners,
+/// so asymmetric shapes such as `Triangle` always point into the symbol.
 fn render_finder_module(
-    x_px: usize,
-    y_px: usize,
-    module_size: usize,
-    shape: &FinderShape,
-    style: &FinderStyle,
+    x: usize,
+    y: usize,
+    corner: FinderCorner,
+    config: &QrRenderConfig,
+    layout: &Layout,
+    defs: &mut GradientDefs,
 ) -> String {
-    // Get the color from the style
-    let color = match style {
-        FinderStyle::Color(c) => c,
+    let (x_px, y_px) = (layout.position(x), layout.position(y));
+    let pattern_size = layout.round(7.0 * layout.module_size);
+    let (x, y, mirror) = match corner {
+        FinderCorner::TopLeft => (x_px, y_px, ""),
+        FinderCorner::TopRight => (layout.round(x_px + pattern_size), y_px, " scale(-1, 1)"),
+        FinderCorner::BottomLeft => (x_px, layout.round(y_px + pattern_size), " scale(1, -1)"),
     };
-    
+
+    // Gradients are defined in symbol coordinates, so undo this group's transform
+    // (mirroring is its own inverse) to keep the finders continuous with the rest of the symbol
+    let inverse = format!("{mirror} translate(-{x}, -{y})");
+    let inverse = Some(inverse.trim_start());
+    let frame_color = defs.paint(config.finder_frame_styling.fill(), &format!("finder-frame-{}", corner.id()), inverse);
+    let eye_color = defs.paint(config.finder_eye_styling.fill(), &format!("finder-eye-{}", corner.id()), inverse);
+    let finder = FinderContext { module_size: layout.module_size, precision: layout.precision };
+
     // Create a group for the finder pattern
-    match shape {
-        // The standard finder pattern shape is the same regardless of the configured FinderShape
-        FinderShape::Square | FinderShape::Triangle => {
-            format!(
-                r#"
-                    <g transform="translate({x}, {y})">
-                        <rect width="{outer_bar_width}" height="{outer_bar_thickness}" fill="{color}"/>
-                        <rect y="{bottom_bar_y}" width="{outer_bar_width}" height="{outer_bar_thickness}" fill="{color}"/>
-                        <rect width="{outer_bar_thickness}" height="{outer_bar_height}" fill="{color}" x="{left_bar_x}" y="{left_bar_y}"/>
-                        <rect width="{outer_bar_thickness}" height="{outer_bar_height}" fill="{color}" x="{right_bar_x}" y="{right_bar_y}"/>
-                        <rect x="{inner_pos}" y="{inner_pos}" width="{inner_size}" height="{inner_size}" fill="{color}"/>
-                    </g>
+    format!(
+        r#"
+                    <g transform="translate({x}, {y}){mirror}">
+{frame}{eye}                    </g>
                 "#,
-                x = x_px,
-                y = y_px,
-                color = color,
-                outer_bar_thickness = module_size, // 1 module thickness
-                outer_bar_width = 7 * module_size, // 7 modules wide
-                outer_bar_height = 5 * module_size, // 5 modules tall (7 total - 1 top - 1 bottom)
-                bottom_bar_y = 6 * module_size,    // Starts at module row 6
-                left_bar_x = 0,                    // Starts at module col 0
-                left_bar_y = 1 * module_size,      // Starts at module row 1
-                right_bar_x = 6 * module_size,     // Starts at module col 6
-                right_bar_y = 1 * module_size,     // Starts at module row 1
-                inner_size = 3 * module_size,      // 3x3 modules for the innermost black square
-                inner_pos = 2 * module_size        // Offset by 2 modules
-            )
-        }
-        FinderShape::Dot => {
-            let center = 3.5 * module_size as f32;
-            let ring_radius = 3.0 * module_size as f32;
-            let ring_stroke_width = 1.0 * module_size as f32;
-            let r_dot = 1.5 * module_size as f32;
+        x = x,
+        y = y,
+        mirror = mirror,
+        frame = config.finder_frame_shape.pattern_shape().render_frame(&finder, &frame_color),
+        eye = config.finder_eye_shape.pattern_shape().render_eye(&finder, &eye_color),
+    )
+}
 
-            format!(
+/// Renders an alignment pattern (5x5 modules) as SVG
+fn render_alignment_module(
+    x: usize,
+    y: usize,
+    layout: &Layout,
+    shape: &AlignmentShape,
+    style: &AlignmentStyle,
+) -> String {
+    // Get the color from the style
+    let AlignmentStyle::Color(color) = style;
+    let color = escape_xml(&color.to_string()).into_owned();
+    let (x_px, y_px) = (layout.position(x), layout.position(y));
+    let modules = |count: f32| layout.round(count * layout.module_size);
+
+    // The pattern is a 1 module thick 5x5 ring around a single centre module
+    let ring_offset = modules(0.5);
+    let ring_size = modules(4.0);
+    let ring_stroke_width = modules(1.0);
+    let centre_offset = modules(2.0);
+    let centre_size = modules(1.0);
+
+    let (ring_corner_radius, centre_corner_radius) = match shape {
+        AlignmentShape::Square => (0.0, 0.0),
+        AlignmentShape::Rounded => (modules(1.0), modules(0.25)),
+        AlignmentShape::Dot => {
+            let center = modules(2.5);
+            return format!(
                 r#"
                     <g transform="translate({x}, {y})">
                         <circle cx='{center}' cy='{center}' r='{ring_radius}' fill='none' stroke='{color}' stroke-width='{ring_stroke_width}'/>
@@ -92,163 +449,436 @@ fn render_finder_module(
                 x = x_px,
                 y = y_px,
                 center = center,
-                ring_radius = ring_radius,
+                ring_radius = modules(2.0),
                 ring_stroke_width = ring_stroke_width,
-                r_dot = r_dot,
+                r_dot = modules(0.5),
                 color = color,
-            )
+            );
         }
-        FinderShape::Rounded => {
-            // For finder shape rounded I want to generate a square with rounded off edges. You can think of it as halfway between the square and dot approachs above
-            let frame_offset = 0.5 * module_size as f32;
-            let frame_size = 6.0 * module_size as f32;
-            let frame_stroke_width = 1.0 * module_size as f32;
-            let frame_corner_radius = 2.0 * module_size as f32;
-
-            let dot_offset = 2.0 * module_size as f32;
-            let dot_size = 3.0 * module_size as f32;
-            let dot_corner_radius = 1.0 * module_size as f32;
+    };
 
-            format!(
-                r#"
+    format!(
+        r#"
                     <g transform="translate({x}, {y})">
-                        <rect x='{frame_offset}' y='{frame_offset}' width='{frame_size}' height='{frame_size}' rx='{frame_corner_radius}' ry='{frame_corner_radius}' fill='none' stroke='{color}' stroke-width='{frame_stroke_width}'/>
-                        <rect x='{dot_offset}' y='{dot_offset}' width='{dot_size}' height='{dot_size}' rx='{dot_corner_radius}' ry='{dot_corner_radius}' fill='{color}'/>
+                        <rect x='{ring_offset}' y='{ring_offset}' width='{ring_size}' height='{ring_size}' rx='{ring_corner_radius}' ry='{ring_corner_radius}' fill='none' stroke='{color}' stroke-width='{ring_stroke_width}'/>
+                        <rect x='{centre_offset}' y='{centre_offset}' width='{centre_size}' height='{centre_size}' rx='{centre_corner_radius}' ry='{centre_corner_radius}' fill='{color}'/>
                     </g>
                 "#,
-                x = x_px,
-                y = y_px,
-                color = color,
-                frame_offset = frame_offset,
-                frame_size = frame_size,
-                frame_stroke_width = frame_stroke_width,
-                frame_corner_radius = frame_corner_radius,
-                dot_offset = dot_offset,
-                dot_size = dot_size,
-                dot_corner_radius = dot_corner_radius,
-            )
+        x = x_px,
+        y = y_px,
+        color = color,
+        ring_offset = ring_offset,
+        ring_size = ring_size,
+        ring_corner_radius = ring_corner_radius,
+        ring_stroke_width = ring_stroke_width,
+        centre_offset = centre_offset,
+        centre_size = centre_size,
+        centre_corner_radius = centre_corner_radius,
+    )
+}
+
+/// Groups modules into runs of horizontally (or vertically) adjacent modules
+///
+/// Returns the (x, y) of each run's first module and the run length, in modules.
+fn module_runs(drawn: &[bool], width: usize, horizontal: bool) -> Vec<(usize, usize, usize)> {
+    let mut runs = Vec::new();
+    for line in 0..width {
+        let is_drawn = |along: usize| {
+            let (x, y) = if horizontal { (along, line) } else { (line, along) };
+            drawn[y * width + x]
+        };
+        let mut along = 0;
+        while along < width {
+            if !is_drawn(along) {
+                along += 1;
+                continue;
+            }
+            let start = along;
+            while along < width && is_drawn(along) {
+                along += 1;
+            }
+            let (x, y) = if horizontal { (start, line) } else { (line, start) };
+            runs.push((x, y, along - start));
         }
     }
+    runs
 }
 
 /// Renders a data module as SVG
-fn render_data_module(x: usize, y: usize, module_size: usize, shape: &DataShape, style: &DataStyle) -> String {
-    let px = x * module_size;
-    let py = y * module_size;
-    // Get the color from the style
-    let color = match style {
-        DataStyle::Color(c) => c,
-    };
-
+fn render_data_module(module: &ModuleContext, shape: &DataShape, color: &str) -> String {
     match shape {
-        DataShape::Rounded => {
-            format!(
-                "  <rect x='{x}' y='{y}' width='{w}' height='{w}' fill='{color}' rx='{r}' ry='{r}'/>\n",
-                x = px,
-                y = py,
-                w = module_size,
-                r = module_size / 4,
-                color = color
-            )
+        DataShape::Square => SquareModule.render(module, color),
+        DataShape::Dot => DotModule.render(module, color),
+        DataShape::Rounded => RoundedModule.render(module, color),
+        DataShape::Triangle => TriangleModule.render(module, color),
+        DataShape::Liquid => LiquidModule.render(module, color),
+        // A lone module is a bar one module long; runs are merged by the caller
+        DataShape::HorizontalLines { thickness } => LineModule { thickness: *thickness, horizontal: true }.render(module, color),
+        DataShape::VerticalLines { thickness } => LineModule { thickness: *thickness, horizontal: false }.render(module, color),
+        DataShape::CustomPath(path) => path.render(module, color),
+        DataShape::Custom(shape) => shape.render(module, color),
+    }
+}
+
+/// Renders the dark modules of the alignment pattern at (x, y) that lie outside `area` as squares
+fn render_uncleared_alignment_modules(
+    matrix: &QrMatrix,
+    x: usize,
+    y: usize,
+    area: LogoArea,
+    layout: &Layout,
+    style: &AlignmentStyle,
+) -> String {
+    let AlignmentStyle::Color(color) = style;
+    let color = escape_xml(&color.to_string()).into_owned();
+    let size = layout.round(layout.module_size);
+    let mut svg = String::new();
+    for module_y in y..y + 5 {
+        for module_x in x..x + 5 {
+            if matrix.is_dark(module_x, module_y) && !area.contains(module_x, module_y) {
+                svg.push_str(&format!(
+                    "  <rect x='{}' y='{}' width='{size}' height='{size}' fill='{color}'/>\n",
+                    layout.position(module_x),
+                    layout.position(module_y),
+                ));
+            }
         }
-        DataShape::Square => {
-            format!(
-                "  <rect x='{x}' y='{y}' width='{w}' height='{w}' fill='{color}'/>\n",
-                x = px,
-                y = py,
-                w = module_size,
-                color = color
-            )
+    }
+    svg
+}
+
+/// Renders the background covering a `size` pixel square SVG
+fn render_background(background: &Background, size: f32, defs: &mut GradientDefs) -> String {
+    let paint = match background {
+        Background::None => return String::new(),
+        Background::Color(color) => escape_xml(&color.to_string()).into_owned(),
+        Background::Gradient(gradient) => defs.define(gradient, "background-gradient", 0.0, size, None),
+        // This function cannot fail, so an image `render_qr_code` refuses is only logged and left out
+        Background::Image(image) => {
+            return match image.data_uri() {
+                Ok(data_uri) => format!(
+                    "  <image width='{size}' height='{size}' preserveAspectRatio='xMidYMid slice' href='{data_uri}'/>\n",
+                    data_uri = escape_xml(&data_uri),
+                ),
+                Err(error) => {
+                    warn!("Leaving out background image: {error}");
+                    String::new()
+                }
+            };
         }
-        DataShape::Dot => {
-            format!(
-                "  <circle cx='{cx}' cy='{cy}' r='{r}' fill='{color}'/>\n",
-                cx = px + module_size / 2,
-                cy = py + module_size / 2,
-                r = module_size / 2,
-                color = color
-            )
+    };
+    format!("  <rect width='{size}' height='{size}' fill='{paint}'/>\n")
+}
+
+/// Renders the logo, and its plate if it has one, centred over the cleared area
+fn render_logo(logo: &Logo, area: LogoArea, data_uri: &str, layout: &Layout, width: usize) -> String {
+    let mut svg = String::new();
+    if let Some(plate) = &logo.plate {
+        svg.push_str(&format!(
+            "  <rect x='{position}' y='{position}' width='{size}' height='{size}' fill='{plate}'/>\n",
+            position = layout.position(area.start),
+            size = layout.round(area.modules as f32 * layout.module_size),
+            plate = escape_xml(&plate.to_string()),
+        ));
+    }
+    let symbol_size = width as f32 * layout.module_size;
+    let logo_size = logo.size * symbol_size;
+    svg.push_str(&format!(
+        "  <image x='{position}' y='{position}' width='{size}' height='{size}' preserveAspectRatio='xMidYMid meet' href='{data_uri}'/>\n",
+        position = layout.round(layout.offset + (symbol_size - logo_size) / 2.0),
+        size = layout.round(logo_size),
+        data_uri = escape_xml(data_uri),
+    ));
+    svg
+}
+
+/// Where the symbol sits in the SVG, in pixels
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    /// Width and height of each module
+    module_size: f32,
+    /// Distance from the edge of the SVG to the symbol
+    offset: f32,
+    /// Width and height of the whole SVG, quiet zone included
+    canvas: f32,
+    /// Decimal places coordinates are written with
+    precision: u8,
+}
+
+impl Layout {
+    /// Lays the symbol out at the configured module size, framed by the quiet zone
+    fn new(width: usize, config: &QrRenderConfig) -> Self {
+        let module_size = if config.module_size.is_finite() && config.module_size > 0.0 {
+            config.module_size
+        } else {
+            warn!("Module size {} is not positive, drawing {DEFAULT_MODULE_SIZE} pixel modules", config.module_size);
+            DEFAULT_MODULE_SIZE
+        };
+        Layout {
+            module_size,
+            offset: config.quiet_zone as f32 * module_size,
+            canvas: (width + 2 * config.quiet_zone) as f32 * module_size,
+            precision: config.precision,
         }
-        DataShape::Triangle => {
-            format!(
-                "  <polygon points='{x1},{y1} {x2},{y2} {x3},{y3}' fill='black'/>\n",
-                x1 = px + module_size / 2,
-                y1 = py,
-                x2 = px,
-                y2 = py + module_size,
-                x3 = px + module_size,
-                y3 = py + module_size
-            )
+    }
+
+    /// Lays the symbol out on a `pixels` wide raster so every module covers whole pixels
+    ///
+    /// The quiet zone absorbs the pixels left over. Returns `None` when the raster
+    /// is too small to give each module at least one pixel.
+    fn snapped(width: usize, config: &QrRenderConfig, pixels: u32) -> Option<Self> {
+        let module_size = pixels as usize / (width + 2 * config.quiet_zone);
+        if module_size == 0 {
+            return None;
+        }
+        let offset = (pixels as usize - width * module_size) / 2;
+        Some(Layout {
+            module_size: module_size as f32,
+            offset: offset as f32,
+            canvas: pixels as f32,
+            precision: config.precision,
+        })
+    }
+
+    fn round(&self, value: f32) -> f32 {
+        round(value, self.precision)
+    }
+
+    /// Returns the pixel position of the edge of the module at `index` along either axis
+    fn position(&self, index: usize) -> f32 {
+        self.round(self.offset + index as f32 * self.module_size)
+    }
+
+    /// Describes the data module at (x, y) for a shape to draw
+    fn module(&self, x: usize, y: usize, neighbours: Neighbours) -> ModuleContext {
+        ModuleContext {
+            x,
+            y,
+            px: self.offset + x as f32 * self.module_size,
+            py: self.offset + y as f32 * self.module_size,
+            size: self.module_size,
+            neighbours,
+            precision: self.precision,
         }
     }
 }
 
 /// Renders a QR code matrix as an SVG string.
-/// Each module is rendered as a 10x10 pixel square.
+/// Each module is rendered as a square of the configured module size, surrounded by the configured quiet zone.
 pub fn render_qr_matrix_as_svg(
-    matrix: &[Vec<bool>],
+    matrix: &QrMatrix,
     user_defined_config: Option<&QrRenderConfig>,
 ) -> String {
-    info!("Starting QR code SVG rendering");
     let default_config = QrRenderConfig::default();
     let config = user_defined_config.unwrap_or(&default_config);
-    let module_size = 10; // pixels per module
-    let width = matrix.len(); // width in modules
-    let svg_size = width * module_size; // total size in pixels
-    info!("Rendering QR code with size {}x{} modules ({}x{} pixels)", width, width, svg_size, svg_size);
+    render_svg(matrix, config, config.svg_size, Layout::new(matrix.width(), config))
+}
+
+/// Renders a QR code matrix as an SVG for rasterizing to `pixels` x `pixels`, sized in
+/// pixels whatever size the config asks for so it fills the raster for every `SvgSize`
+///
+/// Returns the SVG and the width of each module in raster pixels.
+pub(crate) fn render_qr_matrix_for_raster(
+    matrix: &QrMatrix,
+    user_defined_config: Option<&QrRenderConfig>,
+    pixels: u32,
+) -> (String, f32) {
+    let default_config = QrRenderConfig::default();
+    let config = user_defined_config.unwrap_or(&default_config);
+    let mut layout = Layout::new(matrix.width(), config);
+    if config.snap_to_pixels {
+        match Layout::snapped(matrix.width(), config, pixels) {
+            Some(snapped) => layout = snapped,
+            None => warn!("A {pixels} pixel raster is too small to snap modules to whole pixels"),
+        }
+    }
+    let module_pixels = layout.module_size * pixels as f32 / layout.canvas;
+    (render_svg(matrix, config, SvgSize::Pixels, layout), module_pixels)
+}
+
+fn render_svg(matrix: &QrMatrix, config: &QrRenderConfig, svg_size: SvgSize, layout: Layout) -> String {
+    info!("Starting QR code SVG rendering");
+    let width = matrix.width(); // width in modules
+    let svg_pixels = layout.round(layout.canvas); // total size in pixels, including the quiet zone
+    info!("Rendering QR code with size {}x{} modules ({}x{} pixels)", width, width, svg_pixels, svg_pixels);
     
     let mut svg = String::new();
+    let mut defs = GradientDefs {
+        svg: String::new(),
+        origin: layout.offset,
+        size: width as f32 * layout.module_size,
+        masking: false,
+        prefix: escape_xml(&config.id_prefix).into_owned(),
+        precision: layout.precision,
+    };
 
     // SVG header
     svg.push_str(&format!(
-        r#"<svg xmlns='http://www.w3.org/2000/svg' width='{size}' height='{size}' viewBox='0 0 {size} {size}' shape-rendering='geometricPrecision'>\n"#,
-        size = svg_size
+        r#"<svg xmlns='http://www.w3.org/2000/svg'{dimensions} viewBox='0 0 {size} {size}' shape-rendering='geometricPrecision'>\n"#,
+        dimensions = svg_size.attributes(svg_pixels),
+        size = svg_pixels
     ));
 
-    // White background
-    svg.push_str(&format!(
-        r#"  <rect width='{size}' height='{size}' fill='white'/>\n"#,
-        size = svg_size
-    ));
+    svg.push_str(&render_background(&config.background, svg_pixels, &mut defs));
+
+    // Work out which modules the logo clears. This function cannot fail, so problems that
+    // `render_qr_code` refuses are only logged here, and a logo that cannot be placed is left out
+    let logo = config.logo.as_ref().and_then(|logo| {
+        let placed = cleared_area(width, logo).and_then(|area| Ok((area, logo.data_uri()?)));
+        match placed {
+            Ok((area, data_uri)) => {
+                if let Err(error) = check_error_budget(matrix, area) {
+                    warn!("Logo may make the QR code unreadable: {error}");
+                }
+                Some((logo, area, data_uri))
+            }
+            Err(error) => {
+                warn!("Leaving out logo: {error}");
+                None
+            }
+        }
+    });
+    let logo_area = logo.as_ref().map(|(_, area, _)| *area);
+
+    // An inverted code paints the data style over everything but the modules, which are cut out by a mask
+    let foreground = config.inverted.then(|| defs.paint(config.data_styling.fill(), "data-gradient", None));
+    defs.masking = config.inverted;
+    let mask_style = AlignmentStyle::Color(Color::BLACK);
+    let alignment_styling = if config.inverted { &mask_style } else { &config.alignment_styling };
+
+    // Patterns are rendered first so the gradients their fills need are known before the defs are written
+    let mut content = String::new();
 
     // Render finder patterns (7x7 modules each)
-    let finder_pattern_size_px = 7 * module_size;
-    svg.push_str(&render_finder_module(
+    let far_finder = width - 7;
+    content.push_str(&render_finder_module(
         0,
         0,
-        module_size,
-        &config.finder_shape,
-        &config.finder_styling,
+        FinderCorner::TopLeft,
+        config,
+        &layout,
+        &mut defs,
     )); // Top-left
-    svg.push_str(&render_finder_module(
-        svg_size - finder_pattern_size_px,
+    content.push_str(&render_finder_module(
+        far_finder,
         0,
-        module_size,
-        &config.finder_shape,
-        &config.finder_styling,
+        FinderCorner::TopRight,
+        config,
+        &layout,
+        &mut defs,
     )); // Top-right
-    svg.push_str(&render_finder_module(
+    content.push_str(&render_finder_module(
         0,
-        svg_size - finder_pattern_size_px,
-        module_size,
-        &config.finder_shape,
-        &config.finder_styling,
+        far_finder,
+        FinderCorner::BottomLeft,
+        config,
+        &layout,
+        &mut defs,
     )); // Bottom-left
 
+    // Render alignment patterns (5x5 modules each, positioned by their centre module)
+    for (centre_x, centre_y) in alignment_pattern_centres(matrix.version()) {
+        // A pattern reaching under the logo cannot be drawn as a unit, so only its modules
+        // outside the cleared area are drawn, keeping the damage within the error budget
+        if let Some(area) = logo_area.filter(|area| area.overlaps(centre_x - 2, centre_y - 2, 5)) {
+            content.push_str(&render_uncleared_alignment_modules(matrix, centre_x - 2, centre_y - 2, area, &layout, alignment_styling));
+            continue;
+        }
+        content.push_str(&render_alignment_module(
+            centre_x - 2,
+            centre_y - 2,
+            &layout,
+            &config.alignment_shape,
+            alignment_styling,
+        ));
+    }
+
     // Draw data modules
-    for (y, row) in matrix.iter().enumerate() {
-        for (x, &is_dark) in row.iter().enumerate() {
-            // Determine if the current module is part of a finder pattern
-            let is_finder =
-                (x < 7 && y < 7) || (x >= width - 7 && y < 7) || (x < 7 && y >= width - 7);
-
-            // Only render dark data modules
-            if is_dark && !is_finder {
-                svg.push_str(&render_data_module(x, y, module_size, &config.data_shape, &config.data_styling));
+    let data_color = defs.paint(config.data_styling.fill(), "data-gradient", None);
+    let data_modules: Vec<(usize, usize)> = matrix
+        .modules()
+        .filter(|&(x, y, module)| {
+            // Finder and alignment patterns are drawn as units above; only render the remaining dark modules
+            let drawn_as_unit = matches!(module.role, ModuleRole::Finder | ModuleRole::Alignment);
+            let under_logo = logo_area.is_some_and(|area| area.contains(x, y));
+            module.dark && !drawn_as_unit && !under_logo
+        })
+        .map(|(x, y, _)| (x, y))
+        .collect();
+    let mut drawn = vec![false; width * width];
+    for &(x, y) in &data_modules {
+        drawn[y * width + x] = true;
+    }
+    match (&config.data_shape, config.data_render_mode) {
+        (DataShape::Square, DataRenderMode::MergedPath) => {
+            if !data_modules.is_empty() {
+                content.push_str(&format!(
+                    "  <path d='{d}' fill='{color}'/>\n",
+                    d = trace_outline(&data_modules, width, layout.module_size, layout.offset, layout.precision),
+                    color = data_color,
+                ));
             }
         }
+        (DataShape::HorizontalLines { thickness }, _) | (DataShape::VerticalLines { thickness }, _) => {
+            // Bars always span whole runs, so there is nothing further to merge
+            let horizontal = matches!(config.data_shape, DataShape::HorizontalLines { .. });
+            for (x, y, length) in module_runs(&drawn, width, horizontal) {
+                content.push_str(&render_bar(
+                    &layout.module(x, y, Neighbours::default()),
+                    length,
+                    *thickness,
+                    horizontal,
+                    &data_color,
+                ));
+            }
+        }
+        (DataShape::Liquid, DataRenderMode::MergedPath) => {
+            if !data_modules.is_empty() {
+                let d: String = data_modules
+                    .iter()
+                    .map(|&(x, y)| liquid_module_path(&layout.module(x, y, Neighbours::of(&drawn, width, x, y))))
+                    .collect();
+                content.push_str(&format!("  <path d='{d}' fill='{color}'/>\n", d = d, color = data_color));
+            }
+        }
+        (DataShape::CustomPath(path), DataRenderMode::MergedPath) => {
+            // Each copy stays within its own cell, so joining them fills exactly as separate paths would
+            if !data_modules.is_empty() {
+                let d: Vec<String> = data_modules
+                    .iter()
+                    .map(|&(x, y)| {
+                        let module = layout.module(x, y, Neighbours::default());
+                        path.scaled((module.px, module.py), module.size, module.precision)
+                    })
+                    .collect();
+                content.push_str(&format!("  <path d='{d}' fill='{color}'/>\n", d = d.join(" "), color = data_color));
+            }
+        }
+        _ => {
+            for (x, y) in data_modules {
+                let module = layout.module(x, y, Neighbours::of(&drawn, width, x, y));
+                content.push_str(&render_data_module(&module, &config.data_shape, &data_color));
+            }
+        }
+    }
+
+    if let Some(foreground) = foreground {
+        let mask = defs.id("inverted-modules");
+        defs.svg.push_str(&format!(
+            "    <mask id='{mask}'>\n      <rect width='{size}' height='{size}' fill='white'/>\n{content}    </mask>\n",
+            size = svg_pixels,
+        ));
+        content = format!("  <rect width='{svg_pixels}' height='{svg_pixels}' fill='{foreground}' mask='url(#{mask})'/>\n");
     }
 
+    if let Some((logo, area, data_uri)) = &logo {
+        content.push_str(&render_logo(logo, *area, data_uri, &layout, width));
+    }
+
+    if !defs.svg.is_empty() {
+        svg.push_str(&format!("  <defs>\n{}  </defs>\n", defs.svg));
+    }
+    svg.push_str(&content);
     svg.push_str("</svg>\n");
     info!("Completed QR code SVG rendering");
     svg
@@ -257,29 +887,540 @@ pub fn render_qr_matrix_as_svg(
 impl Default for QrRenderConfig {
     fn default() -> Self {
         QrRenderConfig {
-            finder_shape: FinderShape::Square,
+            encoding: QrEncodeOptions::default(),
+            finder_frame_shape: FinderShape::Square,
+            finder_eye_shape: FinderShape::Square,
             data_shape: DataShape::Dot,
-            finder_styling: FinderStyle::Color("green".to_string()),
-            data_styling: DataStyle::Color(("red").to_string())
+            data_render_mode: DataRenderMode::default(),
+            finder_frame_styling: FinderStyle::Color(Color::rgb(0, 128, 0)),
+            finder_eye_styling: FinderStyle::Color(Color::rgb(0, 128, 0)),
+            data_styling: DataStyle::Color(Color::rgb(255, 0, 0)),
+            alignment_shape: AlignmentShape::Square,
+            alignment_styling: AlignmentStyle::Color(Color::rgb(0, 128, 0)),
+            quiet_zone: DEFAULT_QUIET_ZONE,
+            logo: None,
+            module_size: DEFAULT_MODULE_SIZE,
+            precision: DEFAULT_PRECISION,
+            snap_to_pixels: false,
+            svg_size: SvgSize::default(),
+            background: Background::default(),
+            matte: Color::WHITE,
+            inverted: false,
+            verify: false,
+            id_prefix: "qr".to_string(),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::generate_qr_matrix;
-    use crate::svg_rendering::{render_qr_matrix_as_svg, QrRenderConfig};
+    use crate::{generate_qr_matrix, QrEncodeOptions};
+    use crate::svg_rendering::{
+        render_qr_matrix_as_svg, AlignmentShape, Background, AlignmentStyle, DataRenderMode, DataShape, DataStyle, FinderShape,
+        FinderStyle, QrRenderConfig, SvgSize,
+    };
+    use crate::svg_rendering::module_runs;
+    use crate::{FinderContext, FinderPatternShape, ModuleContext, ModuleShape, UnitPath};
+    use crate::{Gradient, GradientStop, Logo, LogoImage, ModuleRole, QrError, QrMatrix, VersionConstraint};
+
+    /// Number of dark modules drawn in the data style, i.e. outside the finder and alignment patterns
+    fn dark_data_modules(matrix: &QrMatrix) -> usize {
+        matrix
+            .modules()
+            .filter(|(_, _, module)| module.dark && !matches!(module.role, ModuleRole::Finder | ModuleRole::Alignment))
+            .count()
+    }
+
+    /// A diamond per module, pointier where it has no dark neighbour to the right
+    struct Diamond;
+
+    impl ModuleShape for Diamond {
+        fn render(&self, module: &ModuleContext, fill: &str) -> String {
+            let (half, right) = (module.size / 2.0, if module.neighbours.is_dark(1, 0) { "wide" } else { "narrow" });
+            format!(
+                "<path class='{right}' d='M{},{} l{half},{half} l-{half},{half} l-{half},-{half} Z' fill='{fill}'/>",
+                module.px + half,
+                module.py,
+            )
+        }
+    }
+
+    struct Plain;
+
+    impl FinderPatternShape for Plain {
+        fn render_frame(&self, finder: &FinderContext, fill: &str) -> String {
+            let size = finder.module_size;
+            format!("<path class='frame' d='M0,0 h{0} v{0} h-{0} Z M{1},{1} v{2} h{2} v-{2} Z' fill='{fill}'/>", 7.0 * size, size, 5.0 * size)
+        }
+
+        fn render_eye(&self, finder: &FinderContext, fill: &str) -> String {
+            let size = finder.module_size;
+            format!("<rect class='eye' x='{0}' y='{0}' width='{1}' height='{1}' fill='{fill}'/>", 2.0 * size, 3.0 * size)
+        }
+    }
 
     #[test]
     fn renders_svg() {
-        let matrix = generate_qr_matrix("https://sith.org");
+        let matrix = generate_qr_matrix("https://sith.org", &QrEncodeOptions::default()).unwrap();
         // Pass a default config to the rendering function
         let config = QrRenderConfig::default();
         let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
-        // Basic checks: SVG header and some black squares
+        // Basic checks: SVG header and modules in the default colors
         assert!(svg.starts_with("<svg"));
-        // Check for both black (data) and blue (finder) modules
-        assert!(svg.contains("fill='red'"));
+        // Check for both red (data) and green (finder) modules
+        assert!(svg.contains("fill='#ff0000'"));
+        assert!(svg.contains("fill='#008000'"));
         assert!(svg.ends_with("</svg>\n"));
     }
+
+    #[test]
+    fn includes_quiet_zone_in_view_box() {
+        let matrix = generate_qr_matrix("https://sith.org", &QrEncodeOptions::default()).unwrap();
+        let modules = matrix.width();
+
+        let svg = render_qr_matrix_as_svg(&matrix, None);
+        let padded = (modules + 8) * 10;
+        assert!(svg.contains(&format!("viewBox='0 0 {padded} {padded}'")));
+        // The top-left finder starts after the 4 module margin
+        assert!(svg.contains("translate(40, 40)"));
+
+        let config = QrRenderConfig { quiet_zone: 0, ..QrRenderConfig::default() };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        let flush = modules * 10;
+        assert!(svg.contains(&format!("viewBox='0 0 {flush} {flush}'")));
+        assert!(svg.contains("translate(0, 0)"));
+    }
+
+    #[test]
+    fn renders_alignment_patterns_as_units() {
+        let options = QrEncodeOptions { version: VersionConstraint::Fixed(7), ..QrEncodeOptions::default() };
+        let matrix = generate_qr_matrix("https://sith.org", &options).unwrap();
+        let config = QrRenderConfig {
+            quiet_zone: 0,
+            data_shape: DataShape::Dot,
+            alignment_shape: AlignmentShape::Dot,
+            alignment_styling: AlignmentStyle::color("purple").unwrap(),
+            ..QrRenderConfig::default()
+        };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+
+        // Version 7 has six alignment patterns, each drawn as a ring and a centre dot
+        assert_eq!(svg.matches("stroke='#800080'").count(), 6);
+        assert_eq!(svg.matches("fill='#800080'").count(), 6);
+        // The pattern centred on (22, 22) starts at module 20
+        assert!(svg.contains("translate(200, 200)"));
+        // None of its modules are drawn as data dots
+        assert!(!svg.contains("cx='225' cy='225'"));
+
+        // Version 1 has no alignment patterns
+        let matrix = generate_qr_matrix("jedi", &QrEncodeOptions::default()).unwrap();
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        assert!(!svg.contains("purple"));
+    }
+
+    #[test]
+    fn styles_finder_frame_and_eye_independently() {
+        let matrix = generate_qr_matrix("https://sith.org", &QrEncodeOptions::default()).unwrap();
+        let config = QrRenderConfig {
+            finder_frame_shape: FinderShape::Rounded,
+            finder_frame_styling: FinderStyle::color("navy").unwrap(),
+            finder_eye_shape: FinderShape::Square,
+            finder_eye_styling: FinderStyle::color("orange").unwrap(),
+            ..QrRenderConfig::default()
+        };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        // One rounded frame and one square eye per finder pattern
+        assert_eq!(svg.matches("rx='20' ry='20' fill='none' stroke='#000080'").count(), 3);
+        assert_eq!(svg.matches("width='30' height='30' fill='#ffa500'").count(), 3);
+
+        let config = QrRenderConfig {
+            finder_frame_shape: FinderShape::Square,
+            finder_eye_shape: FinderShape::Dot,
+            finder_eye_styling: FinderStyle::color("orange").unwrap(),
+            ..QrRenderConfig::default()
+        };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        assert_eq!(svg.matches("r='15' fill='#ffa500'").count(), 3);
+        assert_eq!(svg.matches("height='10' fill='#008000'").count(), 6);
+    }
+
+    #[test]
+    fn renders_triangle_finders_distinctly_from_squares() {
+        let matrix = generate_qr_matrix("https://sith.org", &QrEncodeOptions::default()).unwrap();
+        let config = QrRenderConfig {
+            finder_frame_shape: FinderShape::Triangle,
+            finder_eye_shape: FinderShape::Triangle,
+            ..QrRenderConfig::default()
+        };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        // No square finder bars, one even-odd frame and one eye per finder pattern
+        assert_eq!(svg.matches("height='10' fill='#008000'").count(), 0);
+        assert_eq!(svg.matches("fill='#008000' fill-rule='evenodd'").count(), 3);
+        assert_eq!(svg.matches("M0,0 H70 V50 L50,70 H0 Z").count(), 3);
+        assert_eq!(svg.matches("M20,20 H50 L20,50 Z").count(), 3);
+        // The top-right and bottom-left patterns are mirrored so the cut faces the symbol
+        assert!(svg.contains("scale(-1, 1)"));
+        assert!(svg.contains("scale(1, -1)"));
+    }
+
+    #[test]
+    fn defines_gradients_once_per_fill() {
+        let matrix = generate_qr_matrix("https://sith.org", &QrEncodeOptions::default()).unwrap();
+        let stops = vec![GradientStop::new(0.0, "red".parse().unwrap()), GradientStop::new(1.0, "blue".parse().unwrap())];
+        let config = QrRenderConfig {
+            finder_frame_styling: FinderStyle::Gradient(Gradient::Linear { angle: 90.0, stops: stops.clone() }),
+            data_styling: DataStyle::Gradient(Gradient::Radial { centre: (0.5, 0.5), radius: 0.5, stops }),
+            ..QrRenderConfig::default()
+        };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        assert_eq!(svg.matches("<defs>").count(), 1);
+        // The data gradient spans the 25 module symbol inside the quiet zone
+        assert!(svg.contains("<radialGradient id='qr-data-gradient' gradientUnits='userSpaceOnUse' cx='165' cy='165' r='125'>"));
+        assert!(svg.contains("fill='url(#qr-data-gradient)'"));
+        assert!(!svg.contains("fill='#ff0000'"));
+        // Each finder undoes its own placement so the gradient lines up across the symbol
+        assert!(svg.contains("id='qr-finder-frame-top-left' gradientUnits='userSpaceOnUse' x1='165' y1='40' x2='165' y2='290' gradientTransform='translate(-40, -40)'"));
+        assert!(svg.contains("id='qr-finder-frame-top-right'"));
+        assert!(svg.contains("gradientTransform='scale(-1, 1) translate(-290, -40)'"));
+        assert!(svg.contains("gradientTransform='scale(1, -1) translate(-40, -290)'"));
+        assert!(svg.contains("fill='url(#qr-finder-frame-bottom-left)'"));
+        // Solid eyes need no definitions
+        assert!(!svg.contains("finder-eye-"));
+
+        let svg = render_qr_matrix_as_svg(&matrix, None);
+        assert!(!svg.contains("<defs>"));
+    }
+
+    #[test]
+    fn prefixes_ids_per_config() {
+        let matrix = generate_qr_matrix("https://jedi.org", &QrEncodeOptions::default()).unwrap();
+        let stops = vec![GradientStop::new(0.0, "red".parse().unwrap()), GradientStop::new(1.0, "blue".parse().unwrap())];
+        let render = |id_prefix: &str| {
+            let config = QrRenderConfig {
+                id_prefix: id_prefix.to_string(),
+                finder_frame_styling: FinderStyle::Gradient(Gradient::Linear { angle: 90.0, stops: stops.clone() }),
+                finder_eye_styling: FinderStyle::Gradient(Gradient::Linear { angle: 0.0, stops: stops.clone() }),
+                data_styling: DataStyle::Gradient(Gradient::Linear { angle: 45.0, stops: stops.clone() }),
+                background: Background::Gradient(Gradient::Linear { angle: 0.0, stops: stops.clone() }),
+                ..QrRenderConfig::default()
+            };
+            render_qr_matrix_as_svg(&matrix, Some(&config))
+        };
+        let ids = |svg: &str| -> Vec<String> {
+            svg.match_indices(" id='").map(|(i, _)| svg[i + 5..].split('\'').next().unwrap().to_string()).collect()
+        };
+
+        let (menu, poster) = (render("menu"), render("poster"));
+        let (menu_ids, poster_ids) = (ids(&menu), ids(&poster));
+        assert_eq!(menu_ids.len(), 8);
+        assert!(menu_ids.iter().all(|id| id.starts_with("menu-")), "{menu_ids:?}");
+        assert!(poster_ids.iter().all(|id| id.starts_with("poster-")), "{poster_ids:?}");
+        assert!(menu_ids.iter().all(|id| !poster_ids.contains(id)));
+        // Every reference points at an id of its own document
+        for (svg, ids) in [(&menu, &menu_ids), (&poster, &poster_ids)] {
+            for (i, _) in svg.match_indices("url(#") {
+                let id = svg[i + 5..].split(')').next().unwrap();
+                assert!(ids.iter().any(|own| own == id), "{id}");
+            }
+        }
+    }
+
+    #[test]
+    fn clears_modules_under_logo() {
+        let options = QrEncodeOptions { version: VersionConstraint::Fixed(7), ..QrEncodeOptions::default() };
+        let matrix = generate_qr_matrix("https://sith.org", &options).unwrap();
+        let mut logo = Logo::new(LogoImage::Svg("<svg xmlns='http://www.w3.org/2000/svg'/>".to_string()), 0.2);
+        logo.plate = Some("ivory".parse().unwrap());
+        let config = QrRenderConfig {
+            data_shape: DataShape::Square,
+            data_render_mode: DataRenderMode::PerModule,
+            logo: Some(logo),
+            ..QrRenderConfig::default()
+        };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+
+        // 45 module symbol: 9 modules of logo plus padding clears modules 17 to 27 in both directions
+        assert!(svg.contains("<rect x='210' y='210' width='110' height='110' fill='#fffff0'/>"));
+        assert!(svg.contains("<image x='220' y='220' width='90' height='90' preserveAspectRatio='xMidYMid meet' href='data:image/svg+xml;base64,"));
+        for y in 17..28 {
+            for x in 17..28 {
+                let module = format!("<rect x='{}' y='{}' width='10'", (x + 4) * 10, (y + 4) * 10);
+                assert!(!svg.contains(&module), "module ({x}, {y}) drawn under the logo");
+            }
+        }
+        // The central alignment pattern is hidden, the other five remain
+        assert!(!svg.contains("translate(240, 240)"));
+        assert_eq!(svg.matches("stroke='#008000' stroke-width='10'").count(), 5);
+    }
+
+    #[test]
+    fn keeps_alignment_modules_outside_the_logo() {
+        let options = QrEncodeOptions { version: VersionConstraint::Fixed(14), ..QrEncodeOptions::default() };
+        let matrix = generate_qr_matrix("https://sith.org", &options).unwrap();
+        let config = QrRenderConfig {
+            logo: Some(Logo::new(LogoImage::Svg("<svg xmlns='http://www.w3.org/2000/svg'/>".to_string()), 0.25)),
+            ..QrRenderConfig::default()
+        };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+
+        // 73 module symbol: the logo clears modules 26 to 46, cutting into the four alignment
+        // patterns centred on 26 and 46, which are drawn module by module outside the area
+        assert_eq!(svg.matches("stroke='#008000' stroke-width='10'").count(), 9);
+        let alignment_modules = |x: usize, y: usize| {
+            format!("<rect x='{}' y='{}' width='10' height='10' fill='#008000'/>", (x + 4) * 10, (y + 4) * 10)
+        };
+        for (x, y) in [(24, 24), (28, 24), (24, 28), (48, 48), (44, 48), (48, 44)] {
+            assert!(svg.contains(&alignment_modules(x, y)), "module ({x}, {y}) missing");
+        }
+        for (x, y) in [(26, 26), (28, 28), (44, 44), (46, 46)] {
+            assert!(!svg.contains(&alignment_modules(x, y)), "module ({x}, {y}) drawn under the logo");
+        }
+        // Each of them keeps the 11 of its 17 dark modules that lie outside the area
+        assert_eq!(svg.matches("width='10' height='10' fill='#008000'/>").count(), 4 * 11);
+    }
+
+    #[test]
+    fn merges_square_data_modules_into_one_path() {
+        let options = QrEncodeOptions { version: VersionConstraint::Fixed(20), ..QrEncodeOptions::default() };
+        let matrix = generate_qr_matrix("https://sith.org", &options).unwrap();
+        let per_module = QrRenderConfig {
+            data_shape: DataShape::Square,
+            data_render_mode: DataRenderMode::PerModule,
+            ..QrRenderConfig::default()
+        };
+        let merged = QrRenderConfig { data_shape: DataShape::Square, ..QrRenderConfig::default() };
+        let per_module_svg = render_qr_matrix_as_svg(&matrix, Some(&per_module));
+        let merged_svg = render_qr_matrix_as_svg(&matrix, Some(&merged));
+
+        // 97x97 modules: thousands of data rects collapse into a single path
+        let data_rects = per_module_svg.matches("fill='#ff0000'/>").count();
+        assert!(data_rects > 4000, "{data_rects} data elements");
+        assert_eq!(merged_svg.matches("fill='#ff0000'/>").count(), 1);
+        assert_eq!(merged_svg.matches("<path d='M").count(), 1);
+        assert!(merged_svg.len() * 2 < per_module_svg.len(), "{} vs {} bytes", merged_svg.len(), per_module_svg.len());
+
+        // Other shapes cannot be merged and keep one element per module
+        let dots = render_qr_matrix_as_svg(&matrix, None);
+        assert_eq!(dots.matches("<circle").count(), data_rects);
+    }
+
+    #[test]
+    fn renders_liquid_data_as_connected_blobs() {
+        let matrix = generate_qr_matrix("https://sith.org", &QrEncodeOptions::default()).unwrap();
+        let config = QrRenderConfig { data_shape: DataShape::Liquid, ..QrRenderConfig::default() };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        assert_eq!(svg.matches("fill='#ff0000'/>").count(), 1);
+        assert!(svg.contains("A5,5 0 0 0"), "inner corners should be filleted");
+
+        let config = QrRenderConfig {
+            data_shape: DataShape::Liquid,
+            data_render_mode: DataRenderMode::PerModule,
+            ..QrRenderConfig::default()
+        };
+        let per_module = render_qr_matrix_as_svg(&matrix, Some(&config));
+        let dark_data = dark_data_modules(&matrix);
+        assert_eq!(per_module.matches("fill='#ff0000'/>").count(), dark_data);
+    }
+
+    #[test]
+    fn groups_modules_into_runs() {
+        // Row 0: ##.# / row 1: .##.
+        let drawn = [true, true, false, true, false, true, true, false, false, false, false, false, false, false, false, false];
+        assert_eq!(module_runs(&drawn, 4, true), [(0, 0, 2), (3, 0, 1), (1, 1, 2)]);
+        assert_eq!(module_runs(&drawn, 4, false), [(0, 0, 1), (1, 0, 2), (2, 1, 1), (3, 0, 1)]);
+    }
+
+    #[test]
+    fn renders_one_bar_per_run() {
+        let matrix = generate_qr_matrix("https://sith.org", &QrEncodeOptions::default()).unwrap();
+        let dark_data = dark_data_modules(&matrix);
+        for data_shape in [DataShape::HorizontalLines { thickness: 0.8 }, DataShape::VerticalLines { thickness: 0.8 }] {
+            let config = QrRenderConfig { data_shape, ..QrRenderConfig::default() };
+            let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+            let bars = svg.matches("rx='4' ry='4' fill='#ff0000'/>").count();
+            assert!(bars > 0 && bars < dark_data, "{bars} bars for {dark_data} modules");
+        }
+    }
+
+    #[test]
+    fn draws_custom_module_shapes() {
+        let matrix = generate_qr_matrix("https://sith.org", &QrEncodeOptions::default()).unwrap();
+        let dark_data = dark_data_modules(&matrix);
+        let config = QrRenderConfig {
+            data_shape: DataShape::Custom(Box::new(Diamond)),
+            data_styling: DataStyle::Gradient(Gradient::Linear { angle: 0.0, stops: vec![GradientStop::new(0.0, "red".parse().unwrap())] }),
+            ..QrRenderConfig::default()
+        };
+        // Custom shapes cannot be merged, so each module is drawn on its own with the shared gradient
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        assert_eq!(svg.matches("fill='url(#qr-data-gradient)'").count(), dark_data);
+        assert!(svg.contains("class='wide'") && svg.contains("class='narrow'"));
+        // The dark timing module at (8, 6), offset by the quiet zone
+        assert!(svg.contains("d='M125,100 l5,5"), "{svg}");
+    }
+
+    #[test]
+    fn draws_custom_finder_shapes() {
+        let matrix = generate_qr_matrix("https://sith.org", &QrEncodeOptions::default()).unwrap();
+        let config = QrRenderConfig {
+            finder_frame_shape: FinderShape::Custom(Box::new(Plain)),
+            finder_eye_shape: FinderShape::Custom(Box::new(Plain)),
+            finder_eye_styling: FinderStyle::color("orange").unwrap(),
+            ..QrRenderConfig::default()
+        };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        assert_eq!(svg.matches("class='frame' d='M0,0 h70 v70 h-70 Z M10,10 v50 h50 v-50 Z' fill='#008000'").count(), 3);
+        assert_eq!(svg.matches("class='eye' x='20' y='20' width='30' height='30' fill='#ffa500'").count(), 3);
+        assert!(svg.contains("scale(-1, 1)") && svg.contains("scale(1, -1)"));
+    }
+
+    #[test]
+    fn scales_custom_paths_into_cells() {
+        let matrix = generate_qr_matrix("https://sith.org", &QrEncodeOptions::default()).unwrap();
+        let diamond = UnitPath::parse("M0.5,0 L1,0.5 L0.5,1 L0,0.5 Z").unwrap();
+        let config = QrRenderConfig { data_shape: DataShape::CustomPath(diamond.clone()), ..QrRenderConfig::default() };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        // Merged into one path, with the dark timing module at (8, 6) drawn in its cell
+        assert_eq!(svg.matches("fill='#ff0000'/>").count(), 1);
+        assert!(svg.contains("M125,100 L130,105 L125,110 L120,105 Z"), "{svg}");
+
+        let config = QrRenderConfig {
+            data_shape: DataShape::CustomPath(diamond.clone()),
+            data_render_mode: DataRenderMode::PerModule,
+            finder_frame_shape: FinderShape::CustomPath(UnitPath::parse("M0,0 H1 V1 H0 Z M0.2,0.2 H0.8 V0.8 H0.2 Z").unwrap()),
+            finder_eye_shape: FinderShape::CustomPath(diamond),
+            ..QrRenderConfig::default()
+        };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        assert!(svg.matches("fill='#ff0000'/>").count() > 1);
+        assert_eq!(svg.matches("d='M0,0 L70,0 L70,70 L0,70 Z M14,14 L56,14 L56,56 L14,56 Z' fill='#008000' fill-rule='evenodd'").count(), 3);
+        assert_eq!(svg.matches("d='M35,20 L50,35 L35,50 L20,35 Z' fill='#008000'").count(), 3);
+    }
+
+    #[test]
+    fn sizes_modules_and_svg() {
+        let matrix = generate_qr_matrix("https://sith.org", &QrEncodeOptions::default()).unwrap();
+        let pixels = (matrix.width() + 8) * 3;
+        let header = |svg_size| {
+            let config = QrRenderConfig { module_size: 3.0, svg_size, ..QrRenderConfig::default() };
+            let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+            svg[..svg.find(" shape-rendering").unwrap()].to_string()
+        };
+        let view_box = format!("viewBox='0 0 {pixels} {pixels}'");
+        assert!(header(SvgSize::Pixels).ends_with(&format!("width='{pixels}' height='{pixels}' {view_box}")));
+        assert!(header(SvgSize::ViewBoxOnly).ends_with(&format!("svg' {view_box}")));
+        assert!(header(SvgSize::Millimetres(40.0)).ends_with(&format!("width='40mm' height='40mm' {view_box}")));
+        assert!(header(SvgSize::Percent(100.0)).ends_with(&format!("width='100%' {view_box}")));
+
+        // Modules and patterns follow the module size
+        let config = QrRenderConfig { module_size: 3.0, data_shape: DataShape::Square, ..QrRenderConfig::default() };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        assert!(svg.contains(r#"<g transform="translate(12, 12)">"#));
+        assert!(svg.contains("<rect width='21' height='3' fill='#008000'/>"));
+    }
+
+    #[test]
+    fn lays_out_fractional_module_sizes() {
+        let matrix = generate_qr_matrix("https://sith.org", &QrEncodeOptions::default()).unwrap();
+        let config = QrRenderConfig {
+            module_size: 7.5,
+            quiet_zone: 1,
+            data_shape: DataShape::Dot,
+            ..QrRenderConfig::default()
+        };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        let pixels = (matrix.width() + 2) as f32 * 7.5;
+        assert!(svg.contains(&format!("viewBox='0 0 {pixels} {pixels}'")));
+        assert!(svg.contains(r#"<g transform="translate(7.5, 7.5)">"#));
+        // The dark timing module at (8, 6) is a dot centred in its cell
+        assert!(svg.contains("<circle cx='71.25' cy='56.25' r='3.75' fill='#ff0000'/>"), "{svg}");
+
+        let config = QrRenderConfig { module_size: 10.0 / 3.0, precision: 1, ..QrRenderConfig::default() };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        assert!(svg.contains(r#"<g transform="translate(13.3, 13.3)">"#), "{svg}");
+        assert!(!svg.contains(".33"));
+    }
+
+    #[test]
+    fn paints_backgrounds() {
+        let matrix = generate_qr_matrix("https://sith.org", &QrEncodeOptions::default()).unwrap();
+        let render = |background| {
+            let config = QrRenderConfig { background, ..QrRenderConfig::default() };
+            render_qr_matrix_as_svg(&matrix, Some(&config))
+        };
+        let size = (matrix.width() + 8) * 10;
+
+        assert!(render(Background::default()).contains(&format!("<rect width='{size}' height='{size}' fill='#ffffff'/>")));
+        assert!(render(Background::color("#fafafa").unwrap()).contains("fill='#fafafa'/>"));
+        assert!(!render(Background::None).contains(&format!("<rect width='{size}'")));
+
+        let stops = vec![GradientStop::new(0.0, "white".parse().unwrap()), GradientStop::new(1.0, "grey".parse().unwrap())];
+        let svg = render(Background::Gradient(Gradient::Linear { angle: 0.0, stops }));
+        assert!(svg.contains(&format!("<rect width='{size}' height='{size}' fill='url(#qr-background-gradient)'/>")));
+        // The gradient spans the quiet zone too, unlike the symbol's gradients
+        assert!(svg.contains(&format!("x1='0' y1='{0}' x2='{size}' y2='{0}'", size / 2)), "{svg}");
+
+        let svg = render(Background::Image(LogoImage::Svg("<svg xmlns='http://www.w3.org/2000/svg'/>".to_string())));
+        assert!(svg.contains(&format!(
+            "<image width='{size}' height='{size}' preserveAspectRatio='xMidYMid slice' href='data:image/svg+xml;base64,"
+        )));
+        assert!(!svg.contains("fill='#ffffff'"));
+    }
+
+    #[test]
+    fn cuts_modules_out_of_inverted_codes() {
+        let matrix = generate_qr_matrix("https://sith.org", &QrEncodeOptions::default()).unwrap();
+        let config = QrRenderConfig {
+            inverted: true,
+            data_shape: DataShape::Square,
+            background: Background::color("ivory").unwrap(),
+            ..QrRenderConfig::default()
+        };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        let size = (matrix.width() + 8) * 10;
+
+        // The data color covers the whole canvas, quiet zone included, except where the mask cuts modules out
+        assert!(svg.contains(&format!("<rect width='{size}' height='{size}' fill='#fffff0'/>")));
+        assert!(svg.contains(&format!("<rect width='{size}' height='{size}' fill='#ff0000' mask='url(#qr-inverted-modules)'/>")));
+        let mask = &svg[svg.find("<mask").unwrap()..svg.find("</mask>").unwrap()];
+        assert!(mask.contains(&format!("<rect width='{size}' height='{size}' fill='white'/>")));
+        assert!(mask.contains("<path d='M"));
+        assert!(mask.contains("<rect x='20' y='20' width='30' height='30' fill='#000000'/>"), "{mask}");
+        assert!(!svg.contains("green"));
+
+        let config = QrRenderConfig {
+            inverted: true,
+            data_styling: DataStyle::Gradient(Gradient::Linear { angle: 0.0, stops: vec![GradientStop::new(0.0, "red".parse().unwrap())] }),
+            ..QrRenderConfig::default()
+        };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        assert_eq!(svg.matches("<linearGradient").count(), 1);
+        assert!(svg.contains("fill='url(#qr-data-gradient)' mask='url(#qr-inverted-modules)'"));
+
+        // The mask is prefixed like the gradients, so two inverted codes on one page keep their own
+        let config = QrRenderConfig { inverted: true, id_prefix: "sith".to_string(), ..QrRenderConfig::default() };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        assert!(svg.contains("<mask id='sith-inverted-modules'>"));
+        assert!(svg.contains("mask='url(#sith-inverted-modules)'"));
+        assert!(!svg.contains("'inverted-modules'"));
+    }
+
+    #[test]
+    fn refuses_markup_in_colors() {
+        let injection = "red' onload='alert(1)";
+        assert!(matches!(DataStyle::color(injection), Err(QrError::InvalidConfig(_))));
+        assert!(matches!(FinderStyle::color(injection), Err(QrError::InvalidConfig(_))));
+        assert!(matches!(AlignmentStyle::color(injection), Err(QrError::InvalidConfig(_))));
+        assert!(matches!(Background::color("white\"/><script/>"), Err(QrError::InvalidConfig(_))));
+
+        // Valid colors are written in a canonical form whatever their spelling
+        let matrix = generate_qr_matrix("https://sith.org", &QrEncodeOptions::default()).unwrap();
+        let config = QrRenderConfig {
+            data_styling: DataStyle::color(" RGB(0, 0, 128) ").unwrap(),
+            ..QrRenderConfig::default()
+        };
+        let svg = render_qr_matrix_as_svg(&matrix, Some(&config));
+        assert!(svg.contains("fill='#000080'"));
+        assert!(!svg.contains("RGB"));
+    }
 }
@@ -0,0 +1,464 @@
+use std::fmt;
+use crate::error::QrError;
+use crate::module_shape::{round, FinderContext, FinderPatternShape, ModuleContext, ModuleShape};
+
+/// How far a point may stray outside the unit box before the path is rejected,
+/// allowing for rounding in exported path data
+const TOLERANCE: f32 = 1e-4;
+
+type Point = (f32, f32);
+
+/// A path segment with absolute coordinates, shorthand commands expanded
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Move(Point),
+    Line(Point),
+    Cubic(Point, Point, Point),
+    Quadratic(Point, Point),
+    Arc { radii: (f32, f32), rotation: f32, large_arc: bool, sweep: bool, to: Point },
+    Close,
+}
+
+/// SVG path data drawn in a unit box, from (0, 0) to (1, 1), and scaled into each cell it fills
+///
+/// Parsing normalizes the path to absolute `M`, `L`, `C`, `Q`, `A` and `Z` commands, so it
+/// can be scaled and translated by rewriting its coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitPath {
+    segments: Vec<Segment>,
+}
+
+impl UnitPath {
+    /// Parses the `d` attribute of an SVG path that lies within the unit box, curves and arcs
+    /// included
+    pub fn parse(d: &str) -> Result<Self, QrError> {
+        let mut tokens = Tokens { bytes: d.as_bytes(), position: 0 };
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut current = (0.0, 0.0);
+        let mut subpath_start = (0.0, 0.0);
+        let mut command = None;
+
+        loop {
+            // A command letter may be left out when the previous command repeats
+            let letter = match tokens.command() {
+                Some(letter) => letter,
+                None if tokens.at_end() => break,
+                None => command.ok_or_else(|| invalid(format!("expected a command at position {}", tokens.position)))?,
+            };
+            command = Some(letter);
+            if segments.is_empty() && !matches!(letter, b'M' | b'm') {
+                return Err(invalid("path data must start with a moveto command".to_string()));
+            }
+
+            let relative = letter.is_ascii_lowercase();
+            let origin = if relative { current } else { (0.0, 0.0) };
+            let segment = match letter.to_ascii_uppercase() {
+                b'M' => {
+                    let to = tokens.point(origin)?;
+                    subpath_start = to;
+                    // Further coordinate pairs after a moveto are implicit linetos
+                    command = Some(if relative { b'l' } else { b'L' });
+                    Segment::Move(to)
+                }
+                b'L' => Segment::Line(tokens.point(origin)?),
+                b'H' => Segment::Line((origin.0 + tokens.number()?, current.1)),
+                b'V' => Segment::Line((current.0, origin.1 + tokens.number()?)),
+                b'C' => Segment::Cubic(tokens.point(origin)?, tokens.point(origin)?, tokens.point(origin)?),
+                b'S' => {
+                    let first = match segments.last() {
+                        Some(Segment::Cubic(_, control, _)) => reflect(*control, current),
+                        _ => current,
+                    };
+                    Segment::Cubic(first, tokens.point(origin)?, tokens.point(origin)?)
+                }
+                b'Q' => Segment::Quadratic(tokens.point(origin)?, tokens.point(origin)?),
+                b'T' => {
+                    let control = match segments.last() {
+                        Some(Segment::Quadratic(control, _)) => reflect(*control, current),
+                        _ => current,
+                    };
+                    Segment::Quadratic(control, tokens.point(origin)?)
+                }
+                b'A' => Segment::Arc {
+                    radii: (tokens.number()?.abs(), tokens.number()?.abs()),
+                    rotation: tokens.number()?,
+                    large_arc: tokens.flag()?,
+                    sweep: tokens.flag()?,
+                    to: tokens.point(origin)?,
+                },
+                b'Z' => {
+                    // Closepath takes no arguments, so it cannot repeat implicitly
+                    command = None;
+                    Segment::Close
+                }
+                _ => return Err(invalid(format!("unknown command '{}'", letter as char))),
+            };
+
+            let start = current;
+            current = match &segment {
+                Segment::Move(to) | Segment::Line(to) | Segment::Cubic(_, _, to) | Segment::Quadratic(_, to) => *to,
+                Segment::Arc { to, .. } => *to,
+                Segment::Close => subpath_start,
+            };
+            let outside = |value: f32| !(-TOLERANCE..=1.0 + TOLERANCE).contains(&value);
+            for (x, y) in extremes(start, &segment).into_iter().chain([current]) {
+                if outside(x) || outside(y) {
+                    return Err(invalid(format!("point ({x}, {y}) lies outside the unit box")));
+                }
+            }
+            segments.push(segment);
+        }
+
+        if !segments.iter().any(|segment| !matches!(segment, Segment::Move(_) | Segment::Close)) {
+            return Err(invalid("path data draws nothing".to_string()));
+        }
+        Ok(UnitPath { segments })
+    }
+
+    /// Returns the path data scaled to `size` pixels and moved to `origin`, rounded to `precision` decimal places
+    pub(crate) fn scaled(&self, origin: (f32, f32), size: f32, precision: u8) -> String {
+        self.path_data(
+            |(x, y)| (round(origin.0 + x * size, precision), round(origin.1 + y * size, precision)),
+            |length| round(length * size, precision),
+        )
+    }
+
+    /// Writes the segments as path data, mapping points and lengths through `point` and `length`
+    fn path_data(&self, point: impl Fn(Point) -> Point, length: impl Fn(f32) -> f32) -> String {
+        let pair = |p: Point| {
+            let (x, y) = point(p);
+            format!("{x},{y}")
+        };
+        let commands: Vec<String> = self
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Move(to) => format!("M{}", pair(*to)),
+                Segment::Line(to) => format!("L{}", pair(*to)),
+                Segment::Cubic(first, second, to) => format!("C{} {} {}", pair(*first), pair(*second), pair(*to)),
+                Segment::Quadratic(control, to) => format!("Q{} {}", pair(*control), pair(*to)),
+                Segment::Arc { radii, rotation, large_arc, sweep, to } => format!(
+                    "A{},{} {rotation} {} {} {}",
+                    length(radii.0),
+                    length(radii.1),
+                    u8::from(*large_arc),
+                    u8::from(*sweep),
+                    pair(*to)
+                ),
+                Segment::Close => "Z".to_string(),
+            })
+            .collect();
+        commands.join(" ")
+    }
+}
+
+impl fmt::Display for UnitPath {
+    /// Writes the normalized path data, still in the unit box
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.path_data(|point| point, |length| length))
+    }
+}
+
+/// Fills each data module's cell with the path
+impl ModuleShape for UnitPath {
+    fn render(&self, module: &ModuleContext, fill: &str) -> String {
+        format!(
+            "  <path d='{d}' fill='{fill}'/>\n",
+            d = self.scaled((module.px, module.py), module.size, module.precision),
+        )
+    }
+}
+
+/// Stretches the path over the whole 7x7 frame or the 3x3 eye
+///
+/// Frames are filled even-odd, so a ring drawn as two subpaths keeps its hole whichever
+/// way the subpaths wind.
+impl FinderPatternShape for UnitPath {
+    fn render_frame(&self, finder: &FinderContext, fill: &str) -> String {
+        format!(
+            "                        <path d='{d}' fill='{fill}' fill-rule='evenodd'/>\n",
+            d = self.scaled((0.0, 0.0), 7.0 * finder.module_size, finder.precision),
+        )
+    }
+
+    fn render_eye(&self, finder: &FinderContext, fill: &str) -> String {
+        let offset = 2.0 * finder.module_size;
+        format!(
+            "                        <path d='{d}' fill='{fill}'/>\n",
+            d = self.scaled((offset, offset), 3.0 * finder.module_size, finder.precision),
+        )
+    }
+}
+
+fn invalid(reason: String) -> QrError {
+    QrError::InvalidConfig(format!("invalid path data: {reason}"))
+}
+
+/// Returns the points where a curve or arc starting at `start` reaches furthest along either
+/// axis between its end points, which must lie in the unit box for the whole segment to
+fn extremes(start: Point, segment: &Segment) -> Vec<Point> {
+    match *segment {
+        Segment::Cubic(first, second, to) => {
+            let at = |t: f32| {
+                let along = |p0: f32, p1: f32, p2: f32, p3: f32| {
+                    let u = 1.0 - t;
+                    u * u * u * p0 + 3.0 * u * u * t * p1 + 3.0 * u * t * t * p2 + t * t * t * p3
+                };
+                (along(start.0, first.0, second.0, to.0), along(start.1, first.1, second.1, to.1))
+            };
+            // The derivative is a quadratic in t on each axis, zero at the extremes
+            let roots = |p0: f32, p1: f32, p2: f32, p3: f32| {
+                let (a, b, c) = (-p0 + 3.0 * p1 - 3.0 * p2 + p3, 2.0 * (p0 - 2.0 * p1 + p2), p1 - p0);
+                if a.abs() < f32::EPSILON {
+                    return if b.abs() < f32::EPSILON { vec![] } else { vec![-c / b] };
+                }
+                let discriminant = b * b - 4.0 * a * c;
+                if discriminant < 0.0 {
+                    return vec![];
+                }
+                let root = discriminant.sqrt();
+                vec![(-b + root) / (2.0 * a), (-b - root) / (2.0 * a)]
+            };
+            roots(start.0, first.0, second.0, to.0)
+                .into_iter()
+                .chain(roots(start.1, first.1, second.1, to.1))
+                .filter(|t| (0.0..=1.0).contains(t))
+                .map(at)
+                .collect()
+        }
+        Segment::Quadratic(control, to) => {
+            let at = |t: f32| {
+                let along = |p0: f32, p1: f32, p2: f32| (1.0 - t) * (1.0 - t) * p0 + 2.0 * (1.0 - t) * t * p1 + t * t * p2;
+                (along(start.0, control.0, to.0), along(start.1, control.1, to.1))
+            };
+            let root = |p0: f32, p1: f32, p2: f32| {
+                let denominator = p0 - 2.0 * p1 + p2;
+                (denominator.abs() >= f32::EPSILON).then(|| (p0 - p1) / denominator)
+            };
+            [root(start.0, control.0, to.0), root(start.1, control.1, to.1)]
+                .into_iter()
+                .flatten()
+                .filter(|t| (0.0..=1.0).contains(t))
+                .map(at)
+                .collect()
+        }
+        Segment::Arc { radii, rotation, large_arc, sweep, to } => arc_extremes(start, radii, rotation, large_arc, sweep, to),
+        Segment::Move(_) | Segment::Line(_) | Segment::Close => Vec::new(),
+    }
+}
+
+/// Returns the points where an arc reaches furthest along either axis, found from its centre
+/// as the SVG specification's implementation notes derive it, radii scaled up when too small
+fn arc_extremes(start: Point, radii: (f32, f32), rotation: f32, large_arc: bool, sweep: bool, to: Point) -> Vec<Point> {
+    let (mut rx, mut ry) = radii;
+    // Arcs between coincident points are left out, and arcs without a radius are straight lines
+    if start == to || rx == 0.0 || ry == 0.0 {
+        return Vec::new();
+    }
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let (half_x, half_y) = ((start.0 - to.0) / 2.0, (start.1 - to.1) / 2.0);
+    let (x1, y1) = (cos * half_x + sin * half_y, -sin * half_x + cos * half_y);
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let sign = if large_arc == sweep { -1.0 } else { 1.0 };
+    let coefficient = sign * (numerator / denominator).max(0.0).sqrt();
+    let (cx1, cy1) = (coefficient * rx * y1 / ry, -coefficient * ry * x1 / rx);
+    let centre = (
+        cos * cx1 - sin * cy1 + (start.0 + to.0) / 2.0,
+        sin * cx1 + cos * cy1 + (start.1 + to.1) / 2.0,
+    );
+
+    let angle = |x: f32, y: f32| y.atan2(x);
+    let first = angle((x1 - cx1) / rx, (y1 - cy1) / ry);
+    let mut swept = angle((-x1 - cx1) / rx, (-y1 - cy1) / ry) - first;
+    if sweep && swept < 0.0 {
+        swept += std::f32::consts::TAU;
+    } else if !sweep && swept > 0.0 {
+        swept -= std::f32::consts::TAU;
+    }
+    let on_arc = |theta: f32| {
+        let travelled = if sweep { theta - first } else { first - theta };
+        travelled.rem_euclid(std::f32::consts::TAU) <= swept.abs()
+    };
+    let point = |theta: f32| {
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        (
+            centre.0 + rx * cos * cos_theta - ry * sin * sin_theta,
+            centre.1 + rx * sin * cos_theta + ry * cos * sin_theta,
+        )
+    };
+    let widest = angle(rx * cos, -ry * sin);
+    let tallest = angle(rx * sin, ry * cos);
+    [widest, widest + std::f32::consts::PI, tallest, tallest + std::f32::consts::PI]
+        .into_iter()
+        .filter(|&theta| on_arc(theta))
+        .map(point)
+        .collect()
+}
+
+/// Reflects a control point through the current point, for the smooth curve shorthands
+fn reflect(control: Point, current: Point) -> Point {
+    (2.0 * current.0 - control.0, 2.0 * current.1 - control.1)
+}
+
+/// Reads commands and numbers from path data
+struct Tokens<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Tokens<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn skip_separators(&mut self) {
+        while self.peek().is_some_and(|byte| byte.is_ascii_whitespace() || byte == b',') {
+            self.position += 1;
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_separators();
+        self.peek().is_none()
+    }
+
+    /// Consumes the next command letter, if the next token is one
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        // 'e' and 'E' only ever appear inside numbers
+        let letter = self.peek().filter(|byte| byte.is_ascii_alphabetic() && !matches!(byte, b'e' | b'E'))?;
+        self.position += 1;
+        Some(letter)
+    }
+
+    fn number(&mut self) -> Result<f32, QrError> {
+        self.skip_separators();
+        let start = self.position;
+        let digits = |tokens: &mut Self| {
+            let from = tokens.position;
+            while tokens.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+                tokens.position += 1;
+            }
+            tokens.position > from
+        };
+        if matches!(self.peek(), Some(b'+' | b'-')) {
+            self.position += 1;
+        }
+        let mut has_digits = digits(self);
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            has_digits |= digits(self);
+        }
+        if !has_digits {
+            return Err(invalid(format!("expected a number at position {start}")));
+        }
+        // Only treat 'e' as an exponent when digits follow, so "1e" is never half consumed
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            let mantissa_end = self.position;
+            self.position += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.position += 1;
+            }
+            if !digits(self) {
+                self.position = mantissa_end;
+            }
+        }
+        let text = std::str::from_utf8(&self.bytes[start..self.position]).unwrap_or_default();
+        match text.parse::<f32>() {
+            Ok(number) if number.is_finite() => Ok(number),
+            _ => Err(invalid(format!("'{text}' is not a finite number"))),
+        }
+    }
+
+    fn point(&mut self, origin: Point) -> Result<Point, QrError> {
+        Ok((origin.0 + self.number()?, origin.1 + self.number()?))
+    }
+
+    /// Reads an arc flag, which may be written without a separator before the next value
+    fn flag(&mut self) -> Result<bool, QrError> {
+        self.skip_separators();
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(invalid(format!("expected an arc flag at position {}", self.position))),
+        };
+        self.position += 1;
+        Ok(flag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module_shape::Neighbours;
+
+    #[test]
+    fn normalizes_to_absolute_commands() {
+        let path = UnitPath::parse("m.5 0 l.5.5-.5.5 h-.5 v-0.5 z").unwrap();
+        assert_eq!(path.to_string(), "M0.5,0 L1,0.5 L0.5,1 L0,1 L0,0.5 Z");
+
+        // Shorthand curves are expanded with their reflected control points
+        let path = UnitPath::parse("M0 0.5 C0 0 0.5 0 0.5 0.5 S1 1 1 0.5 Q1 0 0.75 0 T0.5 0").unwrap();
+        assert_eq!(path.to_string(), "M0,0.5 C0,0 0.5,0 0.5,0.5 C0.5,1 1,1 1,0.5 Q1,0 0.75,0 Q0.5,0 0.5,0");
+
+        // Arc flags may be packed against the following coordinates
+        let path = UnitPath::parse("M0,0.5a.5.5 0 1 0 1 0A0.5,0.5,0,1,01e-0,0.5Z").unwrap();
+        assert_eq!(path.to_string(), "M0,0.5 A0.5,0.5 0 1 0 1,0.5 A0.5,0.5 0 1 0 1,0.5 Z");
+    }
+
+    #[test]
+    fn rejects_invalid_path_data() {
+        for d in ["", "  ", "L0,0 L1,1", "M0,0", "M0,0 L1", "M0,0 L1,x", "M0,0 X1,1", "M0,0 L2,0.5", "M0.5,0.5 l-0.6,0", "M0,0 Z 1,1", "M0,0 A1,1 0 2 0 1,1"] {
+            let result = UnitPath::parse(d);
+            assert!(matches!(result, Err(QrError::InvalidConfig(_))), "{d:?} gave {result:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_curves_and_arcs_bulging_out_of_the_box() {
+        // End points inside the box, but the curve or arc between them leaves it
+        for d in [
+            "M0,0 C5,5 -4,-4 1,1",
+            "M0,0.5 C0,-0.5 1,-0.5 1,0.5",
+            "M0,0.5 Q0.5,-0.9 1,0.5",
+            "M0,0 A5,5 0 1 1 1,0",
+            "M0,0 A5,5 0 0 1 1,0",
+            "M0.43,0.57 A1,0.1 45 0 1 0.57,0.43",
+        ] {
+            let result = UnitPath::parse(d);
+            assert!(matches!(result, Err(QrError::InvalidConfig(_))), "{d:?} gave {result:?}");
+        }
+        // Control points and the rest of the ellipse may lie outside as long as the drawn path does not
+        for d in [
+            "M0,0.5 C0,-0.1 1,-0.1 1,0.5",
+            "M0,0.5 Q0.5,-0.5 1,0.5",
+            "M1,0.5 A0.5,0.5 0 0 1 0.5,0 L1,0 Z",
+            "M0,0 A5,5 0 0 0 1,0 L1,1 Z",
+            "M0.5,0 A0.1,0.1 0 0 1 0.5,1 Z",
+        ] {
+            assert!(UnitPath::parse(d).is_ok(), "{d:?} was rejected");
+        }
+    }
+
+    #[test]
+    fn scales_into_cells() {
+        let path = UnitPath::parse("M0.5,0 L1,0.5 L0.5,1 L0,0.5 Z").unwrap();
+        assert_eq!(path.scaled((40.0, 50.0), 10.0, 2), "M45,50 L50,55 L45,60 L40,55 Z");
+
+        let module = ModuleContext { x: 0, y: 0, px: 40.0, py: 50.0, size: 10.0, neighbours: Neighbours::default(), precision: 2 };
+        assert_eq!(path.render(&module, "red"), "  <path d='M45,50 L50,55 L45,60 L40,55 Z' fill='red'/>\n");
+        let finder = FinderContext { module_size: 10.0, precision: 2 };
+        assert!(path.render_frame(&finder, "red").contains("d='M35,0 L70,35 L35,70 L0,35 Z' fill='red' fill-rule='evenodd'"));
+        assert!(path.render_eye(&finder, "red").contains("d='M35,20 L50,35 L35,50 L20,35 Z' fill='red'"));
+
+        let circle = UnitPath::parse("M0,0.5 A0.5,0.5 0 1 1 1,0.5 A0.5,0.5 0 1 1 0,0.5 Z").unwrap();
+        assert_eq!(circle.scaled((0.0, 0.0), 3.0, 2), "M0,1.5 A1.5,1.5 0 1 1 3,1.5 A1.5,1.5 0 1 1 0,1.5 Z");
+        assert_eq!(circle.scaled((0.0, 0.0), 1.0 / 3.0, 3), "M0,0.167 A0.167,0.167 0 1 1 0.333,0.167 A0.167,0.167 0 1 1 0,0.167 Z");
+    }
+}
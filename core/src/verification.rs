@@ -0,0 +1,177 @@
+use image::{imageops, GrayImage, Rgba, RgbaImage};
+use crate::color::Color;
+use crate::decoding::SampledGrid;
+use crate::detection::Detection;
+use crate::error::QrError;
+use crate::format_conversion::{convert_svg_to_format, QrCodeOutput, QrCodeResult, RasterFormat};
+
+/// Pixels per module SVG output is rasterized at for verification, enough for the decoder
+/// to sample every module cleanly whatever size the SVG declares
+const VERIFICATION_MODULE_PIXELS: f32 = 4.0;
+
+/// Flattens an image onto white and reduces it to luminance, as a scanner's camera sees it
+fn to_greyscale(image: &RgbaImage) -> GrayImage {
+    let mut flattened = image.clone();
+    for pixel in flattened.pixels_mut() {
+        let alpha = u32::from(pixel[3]);
+        let [red, green, blue] = [0, 1, 2].map(|channel| {
+            ((u32::from(pixel[channel]) * alpha + 255 * (255 - alpha) + 127) / 255) as u8
+        });
+        *pixel = Rgba([red, green, blue, 255]);
+    }
+    imageops::grayscale(&flattened)
+}
+
+/// Decodes the QR code found in an encoded PNG or JPEG
+///
+/// The image is flattened onto white and reduced to greyscale, then thresholded; the finder
+/// patterns are located by their light ring around a dark eye, whatever their shape, the
+/// module grid is sampled through the perspective they and the bottom right alignment pattern
+/// fix, and the codewords are Reed-Solomon corrected. Inverted codes are inverted back to
+/// dark on light first, as readers that support them do.
+pub fn decode_raster(data: &[u8], inverted: bool) -> Result<String, QrError> {
+    let image = image::load_from_memory(data).map_err(|e| QrError::Verification(format!("unreadable image: {e}")))?;
+    let mut greyscale = to_greyscale(&image.to_rgba8());
+    if inverted {
+        imageops::invert(&mut greyscale);
+    }
+    decode_greyscale(&greyscale)
+}
+
+/// Decodes the QR code found in a dark on light greyscale image
+///
+/// Every plausible grid is tried in turn; the error is the one the most plausible grid failed with.
+pub(crate) fn decode_greyscale(image: &GrayImage) -> Result<String, QrError> {
+    let detection = Detection::new(image)?;
+    let mut first_error = None;
+    for grid in detection.grids() {
+        match grid.decode() {
+            Ok(content) => return Ok(content),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    Err(first_error.unwrap_or_else(|| QrError::Verification("no QR code found in the rendered image".to_string())))
+}
+
+/// Samples the module grid of the QR code found in a dark on light greyscale image, before
+/// any error correction
+///
+/// Of the plausible grids, the first that decodes is returned, or the most plausible one.
+pub(crate) fn sample_grid(image: &GrayImage) -> Result<SampledGrid, QrError> {
+    let detection = Detection::new(image)?;
+    let mut grids = detection.grids().peekable();
+    let Some(best) = grids.peek().cloned() else {
+        return Err(QrError::Verification("no QR code found in the rendered image".to_string()));
+    };
+    Ok(grids.find(|grid| grid.decode().is_ok()).unwrap_or(best))
+}
+
+/// Checks that decoding found `expected`
+pub(crate) fn check_decoded(decoded: Result<String, QrError>, expected: &str) -> Result<(), QrError> {
+    let decoded = decoded?;
+    if decoded == expected {
+        Ok(())
+    } else {
+        Err(QrError::Verification(format!("decoded \"{decoded}\" instead of the input data")))
+    }
+}
+
+impl QrCodeResult {
+    /// Decodes the rendered code and checks that it reads back as `expected`
+    ///
+    /// SVG output is rasterized first, at a few pixels per module, and decoded like
+    /// `decode_raster` does. Fails with `QrError::Verification` when no code is found, it
+    /// cannot be decoded, or it decodes to anything else.
+    pub fn verify(&self, expected: &str) -> Result<(), QrError> {
+        let (image, _) = self.greyscale()?;
+        check_decoded(decode_greyscale(&image), expected)
+    }
+
+    /// Returns the output as a dark on light greyscale image, with its module size in pixels
+    pub(crate) fn greyscale(&self) -> Result<(GrayImage, f32), QrError> {
+        let (data, module_pixels) = match &self.data {
+            QrCodeOutput::Raster(data, _) => (data.clone(), self.module_size),
+            QrCodeOutput::Svg(svg) => {
+                let modules = self.width as f32 / self.module_size;
+                let size = (modules * VERIFICATION_MODULE_PIXELS).ceil() as u32;
+                let QrCodeOutput::Raster(data, _) = convert_svg_to_format(svg, RasterFormat::Png, size, Color::WHITE)? else {
+                    return Err(QrError::Render("Expected raster output".to_string()));
+                };
+                (data, size as f32 / modules)
+            }
+        };
+        let image = image::load_from_memory(&data).map_err(|e| QrError::Verification(format!("unreadable image: {e}")))?;
+        let mut greyscale = to_greyscale(&image.to_rgba8());
+        if self.inverted {
+            imageops::invert(&mut greyscale);
+        }
+        Ok((greyscale, module_pixels))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{render_qr_code, render_qr_code_svg, AlignmentShape, DataShape, DataStyle, FinderShape, QrRenderConfig};
+
+    #[test]
+    fn verifies_raster_and_svg_output() {
+        let data = "https://jedi.org/verify";
+        for format in [RasterFormat::Png, RasterFormat::Jpeg] {
+            let result = render_qr_code(data, None, format, 300).unwrap();
+            assert_eq!(result.verify(data), Ok(()));
+        }
+        let result = render_qr_code_svg(data, None).unwrap();
+        assert_eq!(result.verify(data), Ok(()));
+    }
+
+    #[test]
+    fn verifies_styled_finder_and_alignment_patterns() {
+        let data = "https://jedi.org/styled";
+        for (frame, eye, alignment) in [
+            (FinderShape::Dot, FinderShape::Dot, AlignmentShape::Dot),
+            (FinderShape::Rounded, FinderShape::Rounded, AlignmentShape::Rounded),
+        ] {
+            let config = QrRenderConfig {
+                finder_frame_shape: frame,
+                finder_eye_shape: eye,
+                alignment_shape: alignment,
+                data_shape: DataShape::Dot,
+                ..QrRenderConfig::default()
+            };
+            assert_eq!(render_qr_code(data, Some(&config), RasterFormat::Png, 300).unwrap().verify(data), Ok(()));
+        }
+    }
+
+    #[test]
+    fn decodes_rotated_codes() {
+        let data = "https://jedi.org/rotated";
+        let (image, _) = render_qr_code(data, None, RasterFormat::Png, 300).unwrap().greyscale().unwrap();
+        for rotated in [imageops::rotate90(&image), imageops::rotate180(&image), imageops::rotate270(&image)] {
+            assert_eq!(decode_greyscale(&rotated), Ok(data.to_string()));
+        }
+    }
+
+    #[test]
+    fn reports_codes_that_decode_to_other_data() {
+        let result = render_qr_code("https://jedi.org", None, RasterFormat::Png, 300).unwrap();
+        let error = result.verify("https://sith.org").unwrap_err();
+        assert_eq!(error, QrError::Verification("decoded \"https://jedi.org\" instead of the input data".to_string()));
+    }
+
+    #[test]
+    fn reports_codes_that_do_not_scan() {
+        // Modules the same color as the background leave nothing to find
+        let config = QrRenderConfig {
+            data_shape: DataShape::Square,
+            data_styling: DataStyle::color("white").unwrap(),
+            ..QrRenderConfig::default()
+        };
+        let result = render_qr_code("https://jedi.org", Some(&config), RasterFormat::Png, 300).unwrap();
+        assert!(matches!(result.verify("https://jedi.org"), Err(QrError::Verification(_))));
+
+        assert!(matches!(decode_raster(b"not an image", false), Err(QrError::Verification(_))));
+    }
+}
@@ -12,13 +12,16 @@ const FEATURE_FLAGS = {
     Square: true,
     Dot: true,
     Rounded: true,
-    Triangle: false, // Disabled until implemented
+    Triangle: true,
   },
   DATA_SHAPES: {
     Square: true,
     Dot: true,
     Rounded: false, // Disabled until implemented
     Triangle: false, // Disabled until implemented
+    Liquid: true,
+    HorizontalLines: true,
+    VerticalLines: true,
   },
   COLOR_CUSTOMIZATION: true,
 } as const;